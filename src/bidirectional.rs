@@ -0,0 +1,187 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::Instant;
+
+use crate::board::{Board, Tile, slides};
+use crate::heuristic::DistanceFn;
+use crate::packed_board::{PackedBoard, SearchKey};
+use crate::solve_options::SolveOptions;
+use crate::solve_result::{SolveResult, SolveStats};
+use crate::solver::Error;
+use crate::tile_move::Move;
+
+/// Open state ordered by lowest `g + h` then highest `g`.
+type OpenEntry<K> = Reverse<(usize, Reverse<usize>, K)>;
+
+/// Best cost found for a board and the move which reached it from the side origin.
+struct Node {
+    cost: usize,
+    move_: Option<Move>,
+    closed: bool,
+}
+
+/// One side of the search, `nodes` holds every board seen, open or closed.
+struct Frontier<'a, T: Tile, K, H: ?Sized> {
+    heuristic: &'a H,
+    open: BinaryHeap<OpenEntry<K>>,
+    nodes: HashMap<K, Node>,
+    closed: usize,
+    scratch: Board<T>,
+}
+
+impl<'a, T: Tile, K: SearchKey<T>, H: DistanceFn<T> + ?Sized> Frontier<'a, T, K, H> {
+    fn new(heuristic: &'a H, origin: &Board<T>) -> Self {
+        let mut frontier = Frontier {
+            heuristic,
+            open: BinaryHeap::new(),
+            nodes: HashMap::new(),
+            closed: 0,
+            scratch: origin.clone(),
+        };
+        let distance = heuristic.distance(origin);
+        frontier.open.push(Reverse((distance, Reverse(0), K::encode(origin))));
+        frontier.nodes.insert(K::encode(origin), Node { cost: 0, move_: None, closed: false });
+        frontier
+    }
+
+    /// Lowest `g + h` of the open set, stale entries are dropped on the way.
+    fn min_priority(&mut self) -> Option<usize> {
+        while let Some(Reverse((priority, Reverse(cost), key))) = self.open.peek() {
+            if self.nodes[key].cost == *cost {
+                return Some(*priority)
+            }
+            self.open.pop();
+        }
+        None
+    }
+
+    /// Expands the best open state, returns the best meeting cost found with `other`.
+    fn expand<O: ?Sized>(&mut self, other: &Frontier<T, K, O>, stats: &mut SolveStats) -> Option<(usize, K)> {
+        let Reverse((_, Reverse(cost), key)) = self.open.pop()?;
+        let board = &mut self.scratch;
+        key.decode_into(board);
+        let zero = board.zero();
+        let mut meeting: Option<(usize, K)> = None;
+        stats.nodes_expanded += 1;
+        self.nodes.get_mut(&key).unwrap().closed = true;
+        self.closed += 1;
+
+        for &(move_, cell) in &slides(zero, board.width, board.height) {
+            let cell = match cell {
+                Some(cell) => cell,
+                None => continue,
+            };
+            let child = key.slide(zero, cell);
+            let child_cost = cost + 1;
+            if self.nodes.get(&child).is_some_and(|known| known.cost <= child_cost) {
+                continue
+            }
+
+            let node = Node { cost: child_cost, move_: Some(move_), closed: false };
+            if self.nodes.insert(child.clone(), node).is_some_and(|known| known.closed) {
+                stats.nodes_reopened += 1;
+                self.closed -= 1;
+            }
+            if let Some(&Node { cost: other_cost, .. }) = other.nodes.get(&child) {
+                if meeting.as_ref().is_none_or(|(best, _)| child_cost + other_cost < *best) {
+                    meeting = Some((child_cost + other_cost, child.clone()));
+                }
+            }
+
+            board.data.swap(zero, cell);
+            let priority = child_cost + self.heuristic.distance(board);
+            board.data.swap(zero, cell);
+            self.open.push(Reverse((priority, Reverse(child_cost), child)));
+            stats.nodes_generated += 1;
+        }
+
+        meeting
+    }
+
+    /// Moves from the side origin to `key`.
+    fn path_to(&self, key: &K) -> Vec<Move> {
+        let mut path = Vec::new();
+        let mut board = self.scratch.clone();
+        let mut key = key.clone();
+        while let Some(move_) = self.nodes[&key].move_ {
+            path.push(move_);
+            key.decode_into(&mut board);
+            key = K::encode(&board.moved(move_.inverse()).unwrap());
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Front-to-end bidirectional A*, `backward` estimates the distance to `board`.
+///
+/// The search stops once the best meeting cost is not above the lowest `g + h`
+/// of either open set, which is a lower bound of any path not found yet.
+pub(crate) fn solve<T, F, B>(board: &Board<T>, expected: &Board<T>, options: &SolveOptions, forward: &F, backward: &B)
+    -> Result<SolveResult, Error>
+    where T: Tile, F: DistanceFn<T> + ?Sized, B: DistanceFn<T> + ?Sized
+{
+    if board.data.len() <= PackedBoard::MAX_CELLS {
+        search::<T, PackedBoard, F, B>(board, expected, options, forward, backward)
+    } else {
+        search::<T, Box<[T]>, F, B>(board, expected, options, forward, backward)
+    }
+}
+
+fn search<T, K, F, B>(board: &Board<T>, expected: &Board<T>, options: &SolveOptions, forward: &F, backward: &B)
+    -> Result<SolveResult, Error>
+    where T: Tile, K: SearchKey<T>, F: DistanceFn<T> + ?Sized, B: DistanceFn<T> + ?Sized
+{
+    let start = Instant::now();
+    let mut stats = SolveStats::default();
+    let mut forward_frontier = Frontier::<T, K, F>::new(forward, board);
+    let mut backward_frontier = Frontier::<T, K, B>::new(backward, expected);
+    let mut best: Option<(usize, K)> = None;
+
+    if board.data == expected.data {
+        best = Some((0, K::encode(board)));
+    }
+
+    loop {
+        // the heaps also hold stale entries, the open states are the seen ones not closed
+        stats.tree_nodes = forward_frontier.nodes.len() + backward_frontier.nodes.len();
+        stats.closed_set = forward_frontier.closed + backward_frontier.closed;
+        stats.max_open_set = stats.max_open_set.max(stats.tree_nodes - stats.closed_set);
+
+        let forward_min = forward_frontier.min_priority();
+        let backward_min = backward_frontier.min_priority();
+
+        let (forward_min, backward_min) = match (forward_min, backward_min) {
+            (Some(forward_min), Some(backward_min)) => (forward_min, backward_min),
+            _ => break,
+        };
+        if best.as_ref().is_some_and(|(cost, _)| *cost <= forward_min.max(backward_min)) {
+            break
+        }
+
+        options.check(start, &stats, stats.tree_nodes)?;
+
+        // expand the smallest open set
+        let meeting = if forward_frontier.open.len() <= backward_frontier.open.len() {
+            forward_frontier.expand(&backward_frontier, &mut stats)
+        } else {
+            backward_frontier.expand(&forward_frontier, &mut stats)
+        };
+
+        if let Some((cost, data)) = meeting {
+            if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                best = Some((cost, data));
+            }
+        }
+    }
+
+    let (_, meeting) = best.ok_or(Error::Unsolvable)?;
+
+    // the goal side moves are walked back from the meeting state
+    let mut path = forward_frontier.path_to(&meeting);
+    let backward_path = backward_frontier.path_to(&meeting);
+    path.extend(backward_path.into_iter().rev().map(Move::inverse));
+
+    stats.elapsed = start.elapsed();
+    Ok(SolveResult { path_cost: path.len(), path, heuristic: forward.name(), optimal: true, stats })
+}
@@ -1,21 +1,153 @@
-pub type Tile = u8;
+use std::{error, fmt};
+use std::hash::Hash;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use crate::tile_move::Move;
+
+/// Integer type of the tiles, `u8` holds boards up to 16x16 and keeps the closed set compact.
+pub trait Tile: Copy + Eq + Ord + Hash + fmt::Debug + fmt::Display
+    + FromStr<Err = ParseIntError> + Send + Sync + 'static
+{
+    /// Largest tile number this type holds.
+    const MAX: usize;
+
+    fn index(self) -> usize;
+    fn from_index(index: usize) -> Self;
+}
+
+macro_rules! impl_tile {
+    ($($type:ty),*) => {
+        $(
+            impl Tile for $type {
+                const MAX: usize = <$type>::MAX as usize;
+
+                fn index(self) -> usize {
+                    self as usize
+                }
+
+                fn from_index(index: usize) -> Self {
+                    debug_assert!(index <= Self::MAX as usize);
+                    index as $type
+                }
+            }
+        )*
+    }
+}
+
+impl_tile!(u8, u16, u32);
+
+/// Move at `step` of a sequence which no tile can make.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IllegalMove {
+    pub step: usize,
+    pub move_: Move,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} ({:?}) is illegal, no tile can slide that way", self.step + 1, self.move_)
+    }
+}
+
+impl error::Error for IllegalMove { }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Board {
-    pub data: Box<[Tile]>,
-    pub line_size: usize,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Board<T: Tile = u8> {
+    pub data: Box<[T]>,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Tiles which can slide into the zero at `zero`, with the move they make.
+pub(crate) fn slides(zero: usize, width: usize, height: usize) -> [(Move, Option<usize>); 4] {
+    [
+        (Move::Down, if zero >= width { Some(zero - width) } else { None }),
+        (Move::Up, if zero < width * (height - 1) { Some(zero + width) } else { None }),
+        (Move::Right, if !zero.is_multiple_of(width) { Some(zero - 1) } else { None }),
+        (Move::Left, if zero % width < width - 1 { Some(zero + 1) } else { None }),
+    ]
 }
 
-impl Board {
-    pub fn new(data: Box<[Tile]>, line_size: usize) -> Self {
-        Self { data, line_size }
+/// Cells of a `width` by `height` goal, panics without any cell or with more tiles than `T` holds.
+fn goal_cells<T: Tile>(width: usize, height: usize) -> usize {
+    let len = width.saturating_mul(height);
+    assert!(len.checked_sub(1).is_some_and(|max| max <= T::MAX), "board without cells or too large for its tile type");
+    len
+}
+
+impl<T: Tile> Board<T> {
+    /// Square board of `line_size` by `line_size` tiles.
+    pub fn new(data: Box<[T]>, line_size: usize) -> Self {
+        Self::with_size(data, line_size, line_size)
+    }
+
+    pub fn with_size(data: Box<[T]>, width: usize, height: usize) -> Self {
+        Self { data, width, height }
+    }
+
+    /// Same board with another tile type, `None` if a tile doesn't fit.
+    pub fn convert<U: Tile>(&self) -> Option<Board<U>> {
+        if self.data.iter().any(|tile| tile.index() > U::MAX) {
+            return None
+        }
+        let data = self.data.iter().map(|tile| U::from_index(tile.index())).collect();
+        Some(Board::with_size(data, self.width, self.height))
+    }
+
+    pub(crate) fn zero(&self) -> usize {
+        self.data.iter().position(|tile| tile.index() == 0).unwrap()
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.width == self.height
+    }
+
+    /// Goal with the tiles turning clockwise from the top left corner, the zero ends in the middle.
+    pub fn snail(line_size: usize) -> Self {
+        Self::snail_with_size(line_size, line_size)
+    }
+
+    pub fn snail_with_size(width: usize, height: usize) -> Self {
+        let len = goal_cells::<T>(width, height);
+        let mut data = vec![0; len];
+        let (mut x, mut y) = (0isize, 0isize);
+        let (mut dx, mut dy) = (1isize, 0isize);
+        let (w, h) = (width as isize, height as isize);
+
+        for tile in 1..len {
+            data[(y * w + x) as usize] = tile;
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || nx >= w || ny < 0 || ny >= h || data[(ny * w + nx) as usize] != 0 {
+                // turn right
+                let (ndx, ndy) = (-dy, dx);
+                dx = ndx;
+                dy = ndy;
+            }
+            x += dx;
+            y += dy;
+        }
+
+        Self::with_size(data.into_iter().map(T::from_index).collect(), width, height)
+    }
+
+    /// Goal with the tiles in reading order, the zero ends in the bottom right corner.
+    pub fn row_major(line_size: usize) -> Self {
+        Self::row_major_with_size(line_size, line_size)
+    }
+
+    pub fn row_major_with_size(width: usize, height: usize) -> Self {
+        let len = goal_cells::<T>(width, height);
+        let data = (1..len).chain(Some(0)).map(T::from_index).collect();
+        Self::with_size(data, width, height)
     }
 
     pub fn inversions(&self) -> usize {
         let mut inversions = 0;
-        for (i, &current) in (&self.data[..self.data.len() - 1]).iter().enumerate() {
+        for (i, &current) in self.data[..self.data.len() - 1].iter().enumerate() {
             for &x in self.data.iter().skip(i + 1) {
-                if x < current && current != 0 && x != 0 {
+                if x < current && current.index() != 0 && x.index() != 0 {
                     inversions += 1;
                 }
             }
@@ -23,32 +155,134 @@ impl Board {
         inversions
     }
 
-    pub fn children(&self) -> Vec<Self> {
-        let mut children = Vec::with_capacity(4);
-        let zero = self.data.iter().position(|&x| x == 0).unwrap();
-        let line_size = self.line_size;
-
-        if zero > line_size {
-            let mut board = self.clone();
-            board.data.swap(zero, zero - line_size);
-            children.push(board);
-        }
-        if zero < line_size * (line_size - 1) {
-            let mut board = self.clone();
-            board.data.swap(zero, zero + line_size);
-            children.push(board);
+    /// Board after sliding a tile in the direction of `move_`, `None` if no tile can slide that way.
+    pub(crate) fn moved(&self, move_: Move) -> Option<Self> {
+        let mut board = self.clone();
+        board.apply(move_).ok()?;
+        Some(board)
+    }
+
+    /// Slides a tile in the direction of `move_`, the board is left untouched if none can.
+    pub fn apply(&mut self, move_: Move) -> Result<(), IllegalMove> {
+        let zero = self.zero();
+        let tile = slides(zero, self.width, self.height).iter()
+            .find(|&&(m, _)| m == move_)
+            .and_then(|&(_, tile)| tile)
+            .ok_or(IllegalMove { step: 0, move_ })?;
+
+        self.data.swap(zero, tile);
+        Ok(())
+    }
+
+    /// Applies `moves` in order, on error the board is left as it was before the illegal step.
+    pub fn apply_all(&mut self, moves: &[Move]) -> Result<(), IllegalMove> {
+        for (step, &move_) in moves.iter().enumerate() {
+            self.apply(move_).map_err(|_| IllegalMove { step, move_ })?;
         }
-        if zero % line_size > 0 {
-            let mut board = self.clone();
-            board.data.swap(zero, zero - 1);
-            children.push(board);
+        Ok(())
+    }
+
+    pub fn children(&self) -> Vec<Self> {
+        let zero = self.zero();
+
+        slides(zero, self.width, self.height).iter()
+            .filter_map(|&(_, tile)| tile)
+            .map(|tile| {
+                let mut board = self.clone();
+                board.data.swap(zero, tile);
+                board
+            })
+            .collect()
+    }
+}
+
+/// Writes the size line (`N` for square boards, `W H` otherwise) followed by the aligned tiles lines, as read by `Board::from_reader`.
+impl<T: Tile> fmt::Display for Board<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.data.iter().max().map_or(1, |max| max.to_string().len());
+        if self.is_square() {
+            writeln!(f, "{}", self.width)?;
+        } else {
+            writeln!(f, "{} {}", self.width, self.height)?;
         }
-        if zero % line_size < line_size - 1 {
-            let mut board = self.clone();
-            board.data.swap(zero, zero + 1);
-            children.push(board);
+        for line in self.data.chunks(self.width) {
+            let tiles: Vec<String> = line.iter().map(|tile| format!("{:>digits$}", tile, digits = digits)).collect();
+            writeln!(f, "{}", tiles.join(" "))?;
         }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snail() {
+        let expected = vec![1, 2, 3, 4, 12, 13, 14, 5, 11, 0, 15, 6, 10, 9, 8, 7];
+        assert_eq!(&Board::<u8>::snail(4).data[..], &expected[..]);
 
-        children
+        let expected = vec![1, 2, 3, 8, 0, 4, 7, 6, 5];
+        assert_eq!(&Board::<u8>::snail(3).data[..], &expected[..]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn snail_rectangle() {
+        let expected = vec![1, 2, 3, 4, 10, 11, 0, 5, 9, 8, 7, 6];
+        assert_eq!(&Board::<u8>::snail_with_size(4, 3).data[..], &expected[..]);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let board = Board::<u8>::snail(4);
+        assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+
+        let board = Board::<u16>::snail(20);
+        assert_eq!(board.to_string().parse::<Board<u16>>().unwrap(), board);
+
+        let board = Board::<u8>::snail_with_size(5, 2);
+        assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+    }
+
+    #[test]
+    fn rectangle_children() {
+        let board = Board::<u8>::row_major_with_size(3, 2);
+        let children = board.children();
+
+        assert_eq!(children.len(), 2);
+        assert!(children.contains(&Board::with_size(vec![1, 2, 0, 4, 5, 3].into_boxed_slice(), 3, 2)));
+        assert!(children.contains(&Board::with_size(vec![1, 2, 3, 4, 0, 5].into_boxed_slice(), 3, 2)));
+    }
+
+    #[test]
+    fn apply() {
+        let mut board = Board::<u8>::row_major(3);
+        board.apply_all(&[Move::Down, Move::Right]).unwrap();
+        assert_eq!(&board.data[..], &[1, 2, 3, 4, 0, 5, 7, 8, 6]);
+
+        let error = board.apply_all(&[Move::Up, Move::Up, Move::Left]).unwrap_err();
+        assert_eq!(error, IllegalMove { step: 1, move_: Move::Up });
+        assert_eq!(&board.data[..], &[1, 2, 3, 4, 8, 5, 7, 0, 6]);
+    }
+
+    #[test]
+    fn convert() {
+        let board = Board::<u16>::snail(16);
+        assert_eq!(board.convert::<u8>().unwrap().convert::<u16>().unwrap(), board);
+
+        assert!(Board::<u16>::snail(17).convert::<u8>().is_none());
+    }
+
+    #[test]
+    fn row_major() {
+        let expected = vec![1, 2, 3, 4, 5, 6, 7, 8, 0];
+        assert_eq!(&Board::<u8>::row_major(3).data[..], &expected[..]);
+        assert_eq!(&Board::<u8>::row_major(1).data[..], &[0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "board without cells")]
+    fn empty_goal() {
+        Board::<u8>::snail_with_size(0, 3);
+    }
+}
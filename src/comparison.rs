@@ -0,0 +1,188 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::report::Report;
+
+/// Reports of puzzles solved with every heuristic, written as one table per puzzle
+/// followed by totals per heuristic when there are several puzzles.
+///
+/// The optimal length is not known in general, the shortest solution found stands
+/// for it: an admissible heuristic searched with A* always reaches it.
+#[derive(Debug, Clone, Default)]
+pub struct Comparison {
+    puzzles: Vec<(String, Vec<Report>)>,
+}
+
+/// Totals of one heuristic over the puzzles.
+#[derive(Debug, Clone, Default)]
+struct Totals {
+    solved: usize,
+    matched: usize,
+    inadmissible: usize,
+    expanded: usize,
+    branching: f64,
+    branching_count: usize,
+    time: Duration,
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+impl Comparison {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the reports of the puzzle named `name`, one per heuristic.
+    pub fn push(&mut self, name: impl Into<String>, reports: Vec<Report>) {
+        self.puzzles.push((name.into(), reports));
+    }
+
+    /// Length of the shortest solution found among `reports`.
+    pub fn shortest(reports: &[Report]) -> Option<usize> {
+        reports.iter().filter_map(|report| report.result.as_ref()).map(|result| result.path_cost).min()
+    }
+
+    /// Whether the search claimed an optimal solution which is longer than the
+    /// shortest one, which only happens when the heuristic overestimates.
+    pub fn is_inadmissible(report: &Report, shortest: Option<usize>) -> bool {
+        match (&report.result, shortest) {
+            (Some(result), Some(shortest)) => result.optimal && result.path_cost > shortest,
+            _ => false,
+        }
+    }
+
+    /// Names of the heuristics found inadmissible on any puzzle, in the order they were run.
+    pub fn inadmissible(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        for (_, reports) in &self.puzzles {
+            let shortest = Comparison::shortest(reports);
+            for report in reports.iter().filter(|report| Comparison::is_inadmissible(report, shortest)) {
+                if !names.contains(&report.heuristic) {
+                    names.push(report.heuristic);
+                }
+            }
+        }
+        names
+    }
+
+    fn totals(&self) -> Vec<(&'static str, Totals)> {
+        let mut totals: Vec<(&'static str, Totals)> = Vec::new();
+        for (_, reports) in &self.puzzles {
+            let shortest = Comparison::shortest(reports);
+            for report in reports {
+                let index = match totals.iter().position(|(name, _)| *name == report.heuristic) {
+                    Some(index) => index,
+                    None => {
+                        totals.push((report.heuristic, Totals::default()));
+                        totals.len() - 1
+                    },
+                };
+                let total = &mut totals[index].1;
+                if let Some(result) = &report.result {
+                    total.solved += 1;
+                    total.matched += (Some(result.path_cost) == shortest) as usize;
+                    total.inadmissible += Comparison::is_inadmissible(report, shortest) as usize;
+                    total.expanded += result.stats.nodes_expanded;
+                    total.time += result.stats.elapsed;
+                    if let Some(branching) = result.effective_branching_factor() {
+                        total.branching += branching;
+                        total.branching_count += 1;
+                    }
+                }
+            }
+        }
+        totals
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, reports)) in self.puzzles.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let shortest = Comparison::shortest(reports);
+            match shortest {
+                Some(shortest) => writeln!(f, "{}: shortest solution found {} moves", name, shortest)?,
+                None => writeln!(f, "{}: no solution found", name)?,
+            }
+
+            writeln!(f, "{:<18}  {:<16}  {:>6}  {:>7}  {:>10}  {:>6}  {:>12}  flag",
+                "heuristic", "status", "length", "matched", "expanded", "b*", "time")?;
+            for report in reports {
+                let result = report.result.as_ref();
+                let matched = match (result, shortest) {
+                    (Some(result), Some(shortest)) if result.path_cost == shortest => "yes",
+                    (Some(_), _) => "no",
+                    (None, _) => "-",
+                };
+                let flag = if Comparison::is_inadmissible(report, shortest) { "  inadmissible" } else { "" };
+                writeln!(f, "{:<18}  {:<16}  {:>6}  {:>7}  {:>10}  {:>6}  {:>12}{}",
+                    report.heuristic,
+                    report.status(),
+                    result.map_or("-".to_string(), |result| result.path_cost.to_string()),
+                    matched,
+                    result.map_or("-".to_string(), |result| result.stats.nodes_expanded.to_string()),
+                    result.and_then(|result| result.effective_branching_factor())
+                        .map_or("-".to_string(), |branching| format!("{:.3}", branching)),
+                    result.map_or("-".to_string(), |result| milliseconds(result.stats.elapsed)),
+                    flag)?;
+            }
+        }
+
+        if self.puzzles.len() > 1 {
+            writeln!(f, "\ntotals over {} puzzles", self.puzzles.len())?;
+            writeln!(f, "{:<18}  {:>6}  {:>7}  {:>12}  {:>7}  {:>12}  {:>12}",
+                "heuristic", "solved", "matched", "expanded", "mean b*", "time", "inadmissible")?;
+            for (name, total) in self.totals() {
+                let branching = match total.branching_count {
+                    0 => "-".to_string(),
+                    count => format!("{:.3}", total.branching / count as f64),
+                };
+                writeln!(f, "{:<18}  {:>6}  {:>7}  {:>12}  {:>7}  {:>12}  {:>12}",
+                    name, total.solved, total.matched, total.expanded, branching, milliseconds(total.time), total.inadmissible)?;
+            }
+        }
+
+        let inadmissible = self.inadmissible();
+        if !inadmissible.is_empty() {
+            writeln!(f, "\ninadmissible: {}", inadmissible.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve_result::{SolveResult, SolveStats};
+    use crate::tile_move::Move;
+
+    fn report(heuristic: &'static str, path_cost: usize, optimal: bool) -> Report {
+        let stats = SolveStats { nodes_expanded: 10 * path_cost, nodes_generated: 20 * path_cost, ..SolveStats::default() };
+        let result = SolveResult { path: vec![Move::Up; path_cost], path_cost, heuristic, optimal, stats };
+        Report { result: Some(result), ..Report::new(heuristic, "astar") }
+    }
+
+    #[test]
+    fn inadmissible() {
+        let mut comparison = Comparison::new();
+        comparison.push("first", vec![report("exact", 20, true), report("over", 22, true), report("greedy", 24, false)]);
+        comparison.push("second", vec![report("exact", 8, true), report("over", 8, true), report("greedy", 8, false)]);
+
+        assert_eq!(comparison.inadmissible(), vec!["over"]);
+
+        let table = comparison.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "first: shortest solution found 20 moves");
+        assert!(lines[2].starts_with("exact               solved                20      yes"));
+        assert!(lines[3].starts_with("over                solved                22       no"));
+        assert!(lines[3].ends_with("ms  inadmissible"));
+        assert!(lines[4].ends_with("ms"));
+        assert!(lines[15].starts_with("over                     2        1"));
+        assert!(lines[15].ends_with("             1"));
+        assert_eq!(lines.last(), Some(&"inadmissible: over"));
+    }
+}
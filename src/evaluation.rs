@@ -0,0 +1,177 @@
+use std::str::FromStr;
+use std::{error, fmt};
+
+/// Decimals accepted in a weight such as `1.125`, more would only inflate the priorities.
+pub const MAX_DECIMALS: usize = 3;
+
+/// Largest numerator or denominator of a parsed weight, the weighted priorities
+/// of costs and distances up to 2^43 stay within a 64 bits `usize`.
+pub const MAX_WEIGHT_TERM: usize = 1_000_000;
+
+/// Rational weight applied to the heuristic, `numerator / denominator`. Terms
+/// above `MAX_WEIGHT_TERM` can overflow the priorities.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Weight {
+    pub numerator: usize,
+    pub denominator: usize,
+}
+
+/// Function ordering the open set, `g` is the cost so far and `h` the heuristic distance.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Evaluation {
+    /// `g`, the heuristic is ignored.
+    UniformCost,
+    /// `g + h`
+    #[default]
+    AStar,
+    /// `g + w * h`
+    Weighted(Weight),
+    /// `h`, the cost so far is ignored.
+    Greedy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidEvaluation(pub String);
+
+impl fmt::Display for InvalidEvaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid evaluation {:?}", self.0)
+    }
+}
+
+impl error::Error for InvalidEvaluation { }
+
+impl Weight {
+    pub fn new(numerator: usize, denominator: usize) -> Self {
+        Self { numerator, denominator }
+    }
+}
+
+impl fmt::Display for Weight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// Reads a fraction such as `3/2` or a decimal such as `1.5` with at most `MAX_DECIMALS` decimals,
+/// neither term above `MAX_WEIGHT_TERM`.
+impl FromStr for Weight {
+    type Err = InvalidEvaluation;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidEvaluation(string.to_string());
+
+        let (numerator, denominator) = if let Some((numerator, denominator)) = string.split_once('/') {
+            let numerator = usize::from_str(numerator.trim()).map_err(|_| invalid())?;
+            let denominator = usize::from_str(denominator.trim()).map_err(|_| invalid())?;
+            (numerator, denominator)
+        } else {
+            let (whole, fraction) = string.trim().split_once('.').unwrap_or((string.trim(), ""));
+            if fraction.len() > MAX_DECIMALS {
+                return Err(invalid())
+            }
+            let digits = format!("{}{}", whole, fraction);
+            let numerator = usize::from_str(&digits).map_err(|_| invalid())?;
+            let denominator = 10usize.checked_pow(fraction.len() as u32).ok_or_else(invalid)?;
+            (numerator, denominator)
+        };
+
+        if denominator == 0 || numerator > MAX_WEIGHT_TERM || denominator > MAX_WEIGHT_TERM {
+            return Err(invalid())
+        }
+        Ok(Weight::new(numerator, denominator))
+    }
+}
+
+impl Evaluation {
+    pub fn priority(self, cost: usize, distance: usize) -> usize {
+        match self {
+            Evaluation::UniformCost => cost,
+            Evaluation::AStar => cost + distance,
+            Evaluation::Weighted(weight) => weight.denominator * cost + weight.numerator * distance,
+            Evaluation::Greedy => distance,
+        }
+    }
+
+    /// Whether the first path found is a shortest one, given an admissible heuristic.
+    pub fn is_optimal(self) -> bool {
+        match self {
+            Evaluation::UniformCost | Evaluation::AStar => true,
+            Evaluation::Weighted(weight) => weight.numerator <= weight.denominator,
+            Evaluation::Greedy => false,
+        }
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Evaluation::UniformCost => f.write_str("uniform-cost"),
+            Evaluation::AStar => f.write_str("astar"),
+            Evaluation::Weighted(weight) => write!(f, "weighted:{}", weight),
+            Evaluation::Greedy => f.write_str("greedy"),
+        }
+    }
+}
+
+/// Reads `uniform-cost`, `astar`, `greedy` or `weighted:W` where `W` is a `Weight`.
+impl FromStr for Evaluation {
+    type Err = InvalidEvaluation;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "uniform-cost" => Ok(Evaluation::UniformCost),
+            "astar" => Ok(Evaluation::AStar),
+            "greedy" => Ok(Evaluation::Greedy),
+            _ => match string.split_once(':') {
+                Some(("weighted", weight)) => weight.parse().map(Evaluation::Weighted),
+                _ => Err(InvalidEvaluation(string.to_string())),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_weight() {
+        assert_eq!("3/2".parse(), Ok(Weight::new(3, 2)));
+        assert_eq!("1.5".parse(), Ok(Weight::new(15, 10)));
+        assert_eq!("2".parse(), Ok(Weight::new(2, 1)));
+        assert!("1/0".parse::<Weight>().is_err());
+        assert!("-1".parse::<Weight>().is_err());
+        assert_eq!("1.125".parse(), Ok(Weight::new(1125, 1000)));
+        assert!("1.0001".parse::<Weight>().is_err());
+    }
+
+    #[test]
+    fn extreme_weights() {
+        assert!(format!("{}/1", usize::MAX).parse::<Weight>().is_err());
+        assert!(format!("1/{}", MAX_WEIGHT_TERM + 1).parse::<Weight>().is_err());
+        assert!("1000.001".parse::<Weight>().is_err());
+
+        let heavy = Evaluation::Weighted(format!("{}/1", MAX_WEIGHT_TERM).parse().unwrap());
+        assert_eq!(heavy.priority(3, 2), 2 * MAX_WEIGHT_TERM + 3);
+        let light = Evaluation::Weighted(format!("1/{}", MAX_WEIGHT_TERM).parse().unwrap());
+        assert_eq!(light.priority(2, 5), 2 * MAX_WEIGHT_TERM + 5);
+        assert!(light.is_optimal());
+    }
+
+    #[test]
+    fn parse_evaluation() {
+        for evaluation in [Evaluation::UniformCost, Evaluation::AStar, Evaluation::Greedy, Evaluation::Weighted(Weight::new(3, 2))].iter() {
+            assert_eq!(evaluation.to_string().parse(), Ok(*evaluation));
+        }
+        assert_eq!("weighted:1.5".parse(), Ok(Evaluation::Weighted(Weight::new(15, 10))));
+        assert!("dfs".parse::<Evaluation>().is_err());
+    }
+
+    #[test]
+    fn optimality() {
+        assert!(Evaluation::Weighted(Weight::new(1, 1)).is_optimal());
+        assert!(!Evaluation::Weighted(Weight::new(3, 2)).is_optimal());
+        assert!(!Evaluation::Greedy.is_optimal());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::board::{Board, Tile};
+use crate::solver::is_solvable;
+
+/// Seeded generator of solvable puzzles, the same seed always gives the same puzzles.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// splitmix64, good enough for shuffling and stable across releases.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Uniform random permutation of the goal tiles, drawn again until it is solvable.
+    pub fn shuffle<T: Tile>(&mut self, goal: &Board<T>) -> Board<T> {
+        let mut data: Vec<T> = goal.data.to_vec();
+        loop {
+            // Fisher-Yates
+            for i in (1..data.len()).rev() {
+                let j = self.below(i + 1);
+                data.swap(i, j);
+            }
+
+            let board = Board::with_size(data.clone().into_boxed_slice(), goal.width, goal.height);
+            if is_solvable(&board, goal) {
+                return board
+            }
+        }
+    }
+
+    /// Plays `moves` random moves from the goal without undoing the previous one unless
+    /// it is the only move, the puzzle is never further than `moves` from the goal.
+    /// The walk stops early on a 1x1 board, which has no move.
+    pub fn random_walk<T: Tile>(&mut self, goal: &Board<T>, moves: usize) -> Board<T> {
+        let mut previous: Option<Board<T>> = None;
+        let mut board = goal.clone();
+
+        for _ in 0..moves {
+            let mut children = board.children();
+            if children.len() > 1 {
+                children.retain(|child| Some(child) != previous.as_ref());
+            }
+            if children.is_empty() {
+                break
+            }
+            let child = children.swap_remove(self.below(children.len()));
+            previous = Some(board);
+            board = child;
+        }
+
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+    use crate::heuristic::Manhattan;
+
+    #[test]
+    fn deterministic() {
+        let goal: Board = Board::snail(4);
+
+        assert_eq!(Generator::new(42).shuffle(&goal), Generator::new(42).shuffle(&goal));
+        assert_eq!(Generator::new(42).random_walk(&goal, 30), Generator::new(42).random_walk(&goal, 30));
+        assert_ne!(Generator::new(42).shuffle(&goal), Generator::new(43).shuffle(&goal));
+    }
+
+    #[test]
+    fn shuffle_is_solvable() {
+        let goal: Board = Board::snail(3);
+        let mut generator = Generator::new(7);
+
+        for _ in 0..20 {
+            let board = generator.shuffle(&goal);
+            assert!(Solver::new(board, goal.clone()).is_ok());
+        }
+    }
+
+    #[test]
+    fn random_walk_difficulty() {
+        let goal: Board = Board::snail(3);
+        let mut generator = Generator::new(7);
+
+        assert_eq!(generator.random_walk(&goal, 0), goal);
+        for _ in 0..10 {
+            let board = generator.random_walk(&goal, 12);
+            let solver = Solver::new(board, goal.clone()).unwrap();
+            assert!(solver.solve_ida::<Manhattan>().unwrap().path_cost <= 12);
+        }
+    }
+    #[test]
+    fn random_walk_narrow_boards() {
+        let single: Board = Board::snail_with_size(1, 1);
+        assert_eq!(Generator::new(1).random_walk(&single, 3), single);
+
+        // the only move undoes the previous one
+        let pair: Board = Board::snail_with_size(2, 1);
+        assert_eq!(Generator::new(1).random_walk(&pair, 3), pair.children()[0]);
+        assert_eq!(Generator::new(1).random_walk(&pair, 4), pair);
+
+        let column: Board = Board::row_major_with_size(1, 5);
+        let mut generator = Generator::new(7);
+        for moves in 0..12 {
+            let board = generator.random_walk(&column, moves);
+            let offset = board.zero().abs_diff(column.zero());
+            assert!(offset <= moves && offset % 2 == moves % 2);
+        }
+    }
+}
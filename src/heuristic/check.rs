@@ -0,0 +1,316 @@
+//! Admissibility and consistency checks of a heuristic against exact distances.
+//!
+//! The states around the goal are enumerated breadth first so every one of them
+//! gets its exact distance, the whole state space of boards up to the 3x3. Larger
+//! boards are checked up to a depth and on random states, where only consistency
+//! can be checked: a consistent heuristic which is zero on the goal is admissible.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::board::{Board, Tile, slides};
+use crate::generator::Generator;
+use crate::heuristic::DistanceFn;
+use crate::packed_board::{PackedBoard, SearchKey};
+
+/// Counterexamples kept of each kind, the closest to the goal first.
+pub const MAX_COUNTEREXAMPLES: usize = 3;
+
+/// Part of the state space which is checked.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    /// Depth of the breadth first enumeration from the goal, `None` for the whole state space.
+    pub depth: Option<usize>,
+    /// Random states whose edges are checked for consistency, past `depth`.
+    pub samples: usize,
+    /// Seed of the random states.
+    pub seed: u64,
+}
+
+impl Coverage {
+    /// Every reachable state, only practical up to the 3x3 which has 181440 of them.
+    pub fn exhaustive() -> Self {
+        Self { depth: None, samples: 0, seed: 0 }
+    }
+
+    /// States within `depth` moves of the goal and `samples` random ones.
+    pub fn sampled(depth: usize, samples: usize, seed: u64) -> Self {
+        Self { depth: Some(depth), samples, seed }
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.depth {
+            None => f.write_str("every reachable state")?,
+            Some(depth) => write!(f, "states within {} moves", depth)?,
+        }
+        if self.samples > 0 {
+            write!(f, " and {} random states", self.samples)?;
+        }
+        Ok(())
+    }
+}
+
+/// Board whose estimate is larger than its exact distance to the goal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overestimate<T: Tile = u8> {
+    pub board: Board<T>,
+    pub estimate: usize,
+    pub distance: usize,
+}
+
+/// Neighbour boards, one move apart, whose estimates differ by more than one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inconsistency<T: Tile = u8> {
+    pub board: Board<T>,
+    pub estimate: usize,
+    pub neighbour: Board<T>,
+    pub neighbour_estimate: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckReport<T: Tile = u8> {
+    pub heuristic: &'static str,
+    pub coverage: Coverage,
+    /// States whose exact distance was compared to their estimate.
+    pub states: usize,
+    /// Pairs of neighbour states whose estimates were compared.
+    pub edges: usize,
+    pub overestimate_count: usize,
+    /// First overestimates found, at most `MAX_COUNTEREXAMPLES`.
+    pub overestimates: Vec<Overestimate<T>>,
+    pub inconsistency_count: usize,
+    /// First inconsistencies found, at most `MAX_COUNTEREXAMPLES`.
+    pub inconsistencies: Vec<Inconsistency<T>>,
+}
+
+impl<T: Tile> CheckReport<T> {
+    fn new(heuristic: &'static str, coverage: Coverage) -> Self {
+        Self {
+            heuristic,
+            coverage,
+            states: 0,
+            edges: 0,
+            overestimate_count: 0,
+            overestimates: Vec::new(),
+            inconsistency_count: 0,
+            inconsistencies: Vec::new(),
+        }
+    }
+
+    pub fn is_admissible(&self) -> bool {
+        self.overestimate_count == 0
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistency_count == 0
+    }
+
+    /// Panics with the counterexamples if an overestimate was found, for tests.
+    pub fn assert_admissible(&self) {
+        assert!(self.is_admissible(), "{}", self);
+    }
+
+    /// Panics with the counterexamples if an inconsistency was found, for tests.
+    pub fn assert_consistent(&self) {
+        assert!(self.is_consistent(), "{}", self);
+    }
+
+    fn overestimate(&mut self, board: &Board<T>, estimate: usize, distance: usize) {
+        self.overestimate_count += 1;
+        if self.overestimates.len() < MAX_COUNTEREXAMPLES {
+            self.overestimates.push(Overestimate { board: board.clone(), estimate, distance });
+        }
+    }
+
+    /// Checks the edge between `board` and the board after the tile at `cell` slid into `zero`.
+    fn edge(&mut self, board: &mut Board<T>, estimate: usize, zero: usize, cell: usize, neighbour_estimate: usize) {
+        self.edges += 1;
+        if estimate.abs_diff(neighbour_estimate) <= 1 {
+            return
+        }
+
+        self.inconsistency_count += 1;
+        if self.inconsistencies.len() < MAX_COUNTEREXAMPLES {
+            board.data.swap(zero, cell);
+            let neighbour = board.clone();
+            board.data.swap(zero, cell);
+            self.inconsistencies.push(Inconsistency { board: board.clone(), estimate, neighbour, neighbour_estimate });
+        }
+    }
+}
+
+impl<T: Tile> fmt::Display for CheckReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}: {}, {} states and {} edges checked", self.heuristic, self.coverage, self.states, self.edges)?;
+
+        if self.is_admissible() {
+            writeln!(f, "admissible: yes")?;
+        } else {
+            writeln!(f, "admissible: no, {} overestimates", self.overestimate_count)?;
+            for overestimate in &self.overestimates {
+                writeln!(f, "estimate {} for a board at distance {}", overestimate.estimate, overestimate.distance)?;
+                write!(f, "{}", overestimate.board)?;
+            }
+        }
+
+        if self.is_consistent() {
+            writeln!(f, "consistent: yes")
+        } else {
+            writeln!(f, "consistent: no, {} edges", self.inconsistency_count)?;
+            for inconsistency in &self.inconsistencies {
+                writeln!(f, "estimate {} next to estimate {}", inconsistency.estimate, inconsistency.neighbour_estimate)?;
+                write!(f, "{}{}", inconsistency.board, inconsistency.neighbour)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Compares the estimates of `heuristic` towards `goal` with the exact distances
+/// of the states in `coverage`, and the estimates of neighbour states together.
+pub fn check<T, H>(heuristic: &H, goal: &Board<T>, coverage: Coverage) -> CheckReport<T>
+    where T: Tile, H: DistanceFn<T> + ?Sized
+{
+    if goal.data.len() <= PackedBoard::MAX_CELLS {
+        check_keys::<PackedBoard, T, H>(heuristic, goal, coverage)
+    } else {
+        check_keys::<Box<[T]>, T, H>(heuristic, goal, coverage)
+    }
+}
+
+fn check_keys<K, T, H>(heuristic: &H, goal: &Board<T>, coverage: Coverage) -> CheckReport<T>
+    where K: SearchKey<T>, T: Tile, H: DistanceFn<T> + ?Sized
+{
+    let mut report = CheckReport::new(heuristic.name(), coverage);
+    let mut board = goal.clone();
+
+    // exact distance and estimate of every enumerated state
+    let mut known: HashMap<K, (usize, usize)> = HashMap::new();
+    let root = K::encode(goal);
+    known.insert(root.clone(), (0, heuristic.distance(goal)));
+    let mut layer = vec![root];
+    let mut depth = 0;
+
+    while !layer.is_empty() {
+        let mut next = Vec::new();
+        for key in &layer {
+            key.decode_into(&mut board);
+            let estimate = known[key].1;
+            report.states += 1;
+            if estimate > depth {
+                report.overestimate(&board, estimate, depth);
+            }
+
+            let zero = board.zero();
+            for &(_, cell) in slides(zero, board.width, board.height).iter() {
+                let cell = match cell {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+                let child = key.slide(zero, cell);
+                let child_estimate = match known.get(&child) {
+                    // the edge was checked from the previous layer, the puzzle graph is bipartite
+                    Some(&(distance, _)) if distance + 1 == depth => continue,
+                    Some(&(_, estimate)) => estimate,
+                    None => {
+                        board.data.swap(zero, cell);
+                        let child_estimate = heuristic.distance(&board);
+                        board.data.swap(zero, cell);
+                        if coverage.depth.is_none_or(|max| depth < max) {
+                            known.insert(child.clone(), (depth + 1, child_estimate));
+                            next.push(child);
+                        }
+                        child_estimate
+                    },
+                };
+                report.edge(&mut board, estimate, zero, cell, child_estimate);
+            }
+        }
+        layer = next;
+        depth += 1;
+    }
+
+    let mut generator = Generator::new(coverage.seed);
+    for _ in 0..coverage.samples {
+        let mut sample = generator.shuffle(goal);
+        let estimate = heuristic.distance(&sample);
+        let zero = sample.zero();
+        for &(_, cell) in slides(zero, sample.width, sample.height).iter() {
+            if let Some(cell) = cell {
+                sample.data.swap(zero, cell);
+                let neighbour_estimate = heuristic.distance(&sample);
+                sample.data.swap(zero, cell);
+                report.edge(&mut sample, estimate, zero, cell, neighbour_estimate);
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristic::{Heuristic, HeuristicKind, Manhattan, PatternDatabase};
+
+    /// Twice the Manhattan distance, neither admissible nor consistent.
+    struct Doubled(Manhattan);
+
+    impl DistanceFn for Doubled {
+        fn name(&self) -> &'static str {
+            "doubled"
+        }
+
+        fn distance(&self, current: &Board) -> usize {
+            2 * self.0.distance(current)
+        }
+    }
+
+    #[test]
+    fn counterexamples() {
+        let goal: Board = Board::snail(3);
+        let report = check(&Doubled(Manhattan::new(&goal)), &goal, Coverage::exhaustive());
+
+        assert_eq!(report.states, 181440);
+        assert!(!report.is_admissible());
+        assert!(!report.is_consistent());
+        assert_eq!(report.overestimates.len(), MAX_COUNTEREXAMPLES);
+
+        let first = &report.overestimates[0];
+        assert_eq!((first.estimate, first.distance), (2, 1));
+        assert!(goal.children().contains(&first.board));
+        let inconsistency = &report.inconsistencies[0];
+        assert!(inconsistency.board.children().contains(&inconsistency.neighbour));
+        assert!(report.to_string().contains("admissible: no"));
+    }
+
+    #[test]
+    fn exhaustive_3x3() {
+        for goal in [Board::<u8>::snail(3), Board::row_major(3), Board::snail_with_size(3, 2)].iter() {
+            for &kind in HeuristicKind::ALL {
+                let report = check(&*kind.build(goal).unwrap(), goal, Coverage::exhaustive());
+                report.assert_admissible();
+                // a pattern table keeps the cost of the cheapest blank position
+                if kind != HeuristicKind::PatternDatabase {
+                    report.assert_consistent();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sampled_4x4() {
+        let goal: Board = Board::snail(4);
+        for &kind in HeuristicKind::ALL.iter().filter(|&&kind| kind != HeuristicKind::PatternDatabase) {
+            let report = check(&*kind.build(&goal).unwrap(), &goal, Coverage::sampled(10, 200, 42));
+            report.assert_admissible();
+            report.assert_consistent();
+        }
+
+        // the default groups are slow to build in debug
+        let database = PatternDatabase::with_groups(&goal, &[vec![1, 2, 3, 4, 5], vec![6, 7, 8, 9, 10], vec![11, 12, 13, 14, 15]]).unwrap();
+        check(&database, &goal, Coverage::sampled(10, 200, 42)).assert_admissible();
+    }
+}
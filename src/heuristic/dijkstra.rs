@@ -1,14 +1,20 @@
-use crate::heuristic::Heuristic;
-use crate::board::Board;
+use crate::heuristic::{Heuristic, DistanceFn};
+use crate::board::{Board, Tile};
 
 pub struct Dijkstra;
 
-impl Heuristic for Dijkstra {
-    fn new(_expected: &Board) -> Self {
+impl<T: Tile> Heuristic<T> for Dijkstra {
+    fn new(_expected: &Board<T>) -> Self {
         Dijkstra
     }
+}
 
-    fn distance(&self, _current: &Board) -> usize {
+impl<T: Tile> DistanceFn<T> for Dijkstra {
+    fn name(&self) -> &'static str {
+        "dijkstra"
+    }
+
+    fn distance(&self, _current: &Board<T>) -> usize {
         0
     }
 }
\ No newline at end of file
@@ -1,22 +1,28 @@
-use crate::heuristic::{Heuristic, index_positions};
-use crate::board::Board;
+use crate::heuristic::{Heuristic, DistanceFn, index_positions, tiles_without_blank};
+use crate::board::{Board, Tile};
 
 pub struct Euclidean {
     positions: Box<[(isize, isize)]>,
 }
 
-impl Heuristic for Euclidean {
-    fn new(expected: &Board) -> Self {
+impl<T: Tile> Heuristic<T> for Euclidean {
+    fn new(expected: &Board<T>) -> Self {
         Self{ positions: index_positions(expected) }
     }
+}
+
+impl<T: Tile> DistanceFn<T> for Euclidean {
+    fn name(&self) -> &'static str {
+        "euclidean"
+    }
 
-    fn distance(&self, current: &Board) -> usize {
-        let line_size = current.line_size as isize;
+    fn distance(&self, current: &Board<T>) -> usize {
+        let width = current.width as isize;
         let mut cost = 0;
-        for (i, &tile) in current.data.iter().enumerate() {
+        for (i, tile) in tiles_without_blank(current) {
             let i = i as isize;
-            let (exp_x, exp_y) = self.positions[tile as usize];
-            let (cur_x, cur_y) = (i % line_size, i / line_size);
+            let (exp_x, exp_y) = self.positions[tile.index()];
+            let (cur_x, cur_y) = (i % width, i / width);
             let x_sum = exp_x - cur_x;
             let y_sum = exp_y - cur_y;
             cost += ((x_sum * x_sum + y_sum * y_sum) as f64).sqrt() as usize;
@@ -0,0 +1,87 @@
+use crate::heuristic::{Heuristic, DistanceFn, Manhattan, index_positions};
+use crate::board::{Board, Tile};
+
+/// Manhattan distance plus two moves for each tile that has to leave its goal
+/// row (or column) to let the others pass.
+pub struct LinearConflict {
+    manhattan: Manhattan,
+    positions: Box<[(isize, isize)]>,
+}
+
+/// Number of tiles to remove from `line` so that the rest is increasing.
+fn line_conflicts(line: &[isize]) -> usize {
+    // longest increasing subsequence, the line is never longer than a board side
+    let mut lengths = vec![1; line.len()];
+    for i in 0..line.len() {
+        for j in 0..i {
+            if line[j] < line[i] && lengths[j] + 1 > lengths[i] {
+                lengths[i] = lengths[j] + 1;
+            }
+        }
+    }
+    line.len() - lengths.iter().max().cloned().unwrap_or(0)
+}
+
+impl<T: Tile> Heuristic<T> for LinearConflict {
+    fn new(expected: &Board<T>) -> Self {
+        Self{ manhattan: Manhattan::new(expected), positions: index_positions(expected) }
+    }
+}
+
+impl<T: Tile> DistanceFn<T> for LinearConflict {
+    fn name(&self) -> &'static str {
+        "linear_conflict"
+    }
+
+    fn distance(&self, current: &Board<T>) -> usize {
+        let (width, height) = (current.width, current.height);
+        let mut conflicts = 0;
+        let mut line = Vec::with_capacity(width.max(height));
+
+        for y in 0..height {
+            line.clear();
+            for x in 0..width {
+                let tile = current.data[y * width + x];
+                let (exp_x, exp_y) = self.positions[tile.index()];
+                if tile.index() != 0 && exp_y == y as isize {
+                    line.push(exp_x);
+                }
+            }
+            conflicts += line_conflicts(&line);
+        }
+
+        for x in 0..width {
+            line.clear();
+            for y in 0..height {
+                let tile = current.data[y * width + x];
+                let (exp_x, exp_y) = self.positions[tile.index()];
+                if tile.index() != 0 && exp_x == x as isize {
+                    line.push(exp_y);
+                }
+            }
+            conflicts += line_conflicts(&line);
+        }
+
+        self.manhattan.distance(current) + 2 * conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristic::check::{check, Coverage};
+
+    #[test]
+    fn exact_distances_2x2() {
+        let goal: Board = Board::snail(2);
+        let heuristic = LinearConflict::new(&goal);
+        for child in goal.children() {
+            assert_eq!(heuristic.distance(&child), 1);
+        }
+
+        let report = check(&heuristic, &goal, Coverage::exhaustive());
+        assert_eq!(report.states, 12);
+        report.assert_admissible();
+        report.assert_consistent();
+    }
+}
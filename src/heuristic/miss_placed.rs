@@ -1,22 +1,28 @@
-use crate::heuristic::{Heuristic, index_positions};
-use crate::board::Board;
+use crate::heuristic::{Heuristic, DistanceFn, index_positions, tiles_without_blank};
+use crate::board::{Board, Tile};
 
 pub struct MissPlaced {
     positions: Box<[(isize, isize)]>,
 }
 
-impl Heuristic for MissPlaced {
-    fn new(expected: &Board) -> Self {
+impl<T: Tile> Heuristic<T> for MissPlaced {
+    fn new(expected: &Board<T>) -> Self {
         Self{ positions: index_positions(expected) }
     }
+}
+
+impl<T: Tile> DistanceFn<T> for MissPlaced {
+    fn name(&self) -> &'static str {
+        "miss_placed"
+    }
 
-    fn distance(&self, current: &Board) -> usize {
-        let line_size = current.line_size as isize;
+    fn distance(&self, current: &Board<T>) -> usize {
+        let width = current.width as isize;
         let mut cost = 0;
-        for (i, &tile) in current.data.iter().enumerate() {
+        for (i, tile) in tiles_without_blank(current) {
             let i = i as isize;
-            let (exp_x, exp_y) = self.positions[tile as usize];
-            if i - exp_x - exp_y * line_size != 0 {
+            let (exp_x, exp_y) = self.positions[tile.index()];
+            if i - exp_x - exp_y * width != 0 {
                 cost += 1;
             }
         }
@@ -3,26 +3,46 @@ mod dijkstra;
 mod euclidean;
 mod miss_placed;
 mod out_of_raw;
+mod linear_conflict;
+pub mod pattern_database;
+pub mod check;
+pub mod walking_distance;
+mod registry;
 
 pub use self::manhattan::Manhattan;
 pub use self::dijkstra::Dijkstra;
 pub use self::euclidean::Euclidean;
 pub use self::miss_placed::MissPlaced;
 pub use self::out_of_raw::OutOfRaw;
-use crate::board::Board;
+pub use self::linear_conflict::LinearConflict;
+pub use self::pattern_database::PatternDatabase;
+pub use self::walking_distance::WalkingDistance;
+pub use self::registry::{HeuristicKind, UnknownHeuristic, BuildError};
+use crate::board::{Board, Tile};
 
-fn index_positions(board: &Board) -> Box<[(isize, isize)]> {
-        let line_size = board.line_size;
+fn index_positions<T: Tile>(board: &Board<T>) -> Box<[(isize, isize)]> {
+        let width = board.width;
         let mut positions = vec![(0, 0); board.data.len()];
 
         for (i, &p) in board.data.iter().enumerate() {
-            positions[p as usize] = ((i % line_size) as isize, (i / line_size) as isize);
+            positions[p.index()] = ((i % width) as isize, (i / width) as isize);
         }
 
         positions.into_boxed_slice()
 }
 
-pub trait Heuristic {
-    fn new(expected: &Board) -> Self;
-    fn distance(&self, current: &Board) -> usize;
+/// Tiles with their cell, the blank left out: it moves with every tile so
+/// counting its distance would overestimate.
+fn tiles_without_blank<T: Tile>(board: &Board<T>) -> impl Iterator<Item = (usize, T)> + '_ {
+    board.data.iter().copied().enumerate().filter(|&(_, tile)| tile.index() != 0)
+}
+
+/// Object safe part of a heuristic, usable as a `Box<dyn DistanceFn>`.
+pub trait DistanceFn<T: Tile = u8> {
+    fn name(&self) -> &'static str;
+    fn distance(&self, current: &Board<T>) -> usize;
+}
+
+pub trait Heuristic<T: Tile = u8>: DistanceFn<T> {
+    fn new(expected: &Board<T>) -> Self where Self: Sized;
 }
\ No newline at end of file
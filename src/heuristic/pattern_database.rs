@@ -0,0 +1,433 @@
+use std::fs::File;
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::path::Path;
+use std::time::Instant;
+use std::{error, fmt};
+
+use crate::heuristic::{Heuristic, DistanceFn};
+use crate::board::{Board, Tile, slides};
+
+const MAGIC: &[u8; 4] = b"NPDB";
+const VERSION: u8 = 3;
+
+/// Upper bound on the number of (pattern, blank) states explored for one group.
+const MAX_STATES: usize = 1 << 28;
+
+/// Number of states explored between two looks at the deadline.
+const DEADLINE_PERIOD: usize = 1 << 16;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidGroups,
+    GroupTooLarge,
+    StaleDatabase,
+    CorruptedDatabase,
+    Timeout,
+    Io(io::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidGroups => write!(f, "pattern groups must be disjoint non-zero tiles"),
+            Error::GroupTooLarge => write!(f, "pattern group is too large for this board"),
+            Error::StaleDatabase => write!(f, "pattern database doesn't match the goal or the groups"),
+            Error::CorruptedDatabase => write!(f, "corrupted pattern database"),
+            Error::Timeout => write!(f, "timeout while generating the pattern database"),
+            Error::Io(error) => write!(f, "io error; {}", error),
+        }
+    }
+}
+
+impl error::Error for Error { }
+
+struct Pattern<T: Tile> {
+    tiles: Box<[T]>,
+    /// Exact number of pattern tile moves, indexed by the tiles positions.
+    costs: Box<[u8]>,
+}
+
+/// Additive disjoint pattern database, the cost of every group only counts
+/// the moves of its own tiles so the sum stays admissible. It is not consistent,
+/// a group costs as much as with the blank at its cheapest position.
+pub struct PatternDatabase<T: Tile = u8> {
+    expected: Board<T>,
+    patterns: Vec<Pattern<T>>,
+}
+
+fn states_count(cells: usize, tiles: usize) -> Option<usize> {
+    (0..tiles).try_fold(1usize, |acc, _| acc.checked_mul(cells))
+}
+
+/// Splits the goal tiles, in goal order, into the largest groups that can be generated.
+///
+/// A group is indexed by the cells of its tiles and of the blank, `cells^(size + 1)`
+/// states up to `MAX_STATES`, so the 4x4 gets a 6-6-3 partition and a group of 7 or 8
+/// tiles, as in a 7-8 partition, is rejected with `Error::GroupTooLarge`. Even with a
+/// permutation index the 8 tile group would take about a gigabyte and minutes to generate.
+pub fn default_groups<T: Tile>(expected: &Board<T>) -> Vec<Vec<T>> {
+    let cells = expected.data.len();
+    let mut size = 1;
+    while size + 1 < cells && states_count(cells, size + 2).is_some_and(|n| n <= MAX_STATES) {
+        size += 1;
+    }
+
+    let tiles: Vec<T> = expected.data.iter().cloned().filter(|t| t.index() != 0).collect();
+    tiles.chunks(size).map(|chunk| chunk.to_vec()).collect()
+}
+
+fn index_of(positions: &[usize], cells: usize) -> usize {
+    positions.iter().rev().fold(0, |index, &p| index * cells + p)
+}
+
+fn generate<T: Tile>(expected: &Board<T>, tiles: &[T], deadline: Option<Instant>) -> Result<Box<[u8]>, Error> {
+    let cells = expected.data.len();
+    let size = tiles.len();
+    let table_len = states_count(cells, size).unwrap();
+
+    let goal_positions: Vec<usize> = tiles.iter()
+        .map(|&t| expected.data.iter().position(|&x| x == t).unwrap())
+        .collect();
+    let goal_zero = expected.zero();
+
+    let mut costs = vec![u8::MAX; table_len];
+    let mut visited = vec![0u64; table_len * cells / 64 + 1];
+    let is_visited = |visited: &[u64], state: usize| visited[state / 64] & (1 << (state % 64)) != 0;
+
+    // breadth first search by cost layers, moving a tile outside the pattern is free
+    let mut frontier = vec![(index_of(&goal_positions, cells) * cells + goal_zero) as u32];
+    let mut positions = vec![0; size];
+    let mut cost = 0u8;
+    let mut explored = 0usize;
+    while !frontier.is_empty() {
+        let mut next = Vec::new();
+
+        while let Some(state) = frontier.pop() {
+            let state = state as usize;
+            if is_visited(&visited, state) {
+                continue
+            }
+            visited[state / 64] |= 1 << (state % 64);
+
+            explored += 1;
+            if explored.is_multiple_of(DEADLINE_PERIOD) && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(Error::Timeout)
+            }
+
+            let (index, zero) = (state / cells, state % cells);
+            if costs[index] == u8::MAX {
+                costs[index] = cost;
+            }
+
+            let mut rest = index;
+            for position in positions.iter_mut() {
+                *position = rest % cells;
+                rest /= cells;
+            }
+
+            for &(_, cell) in &slides(zero, expected.width, expected.height) {
+                let cell = match cell {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+                match positions.iter().position(|&p| p == cell) {
+                    Some(slot) => {
+                        let weight = states_count(cells, slot).unwrap();
+                        let child = (index - cell * weight + zero * weight) * cells + cell;
+                        if !is_visited(&visited, child) {
+                            next.push(child as u32);
+                        }
+                    },
+                    None => {
+                        let child = index * cells + cell;
+                        if !is_visited(&visited, child) {
+                            frontier.push(child as u32);
+                        }
+                    },
+                }
+            }
+        }
+
+        frontier = next;
+        cost += 1;
+    }
+
+    Ok(costs.into_boxed_slice())
+}
+
+fn read_u32<R: Read>(read: &mut R) -> Result<u32, Error> {
+    let mut bytes = [0; 4];
+    read.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Tiles are stored as `u32` whatever their type, a cache doesn't depend on the tile width.
+fn write_tiles<W: Write, T: Tile>(write: &mut W, tiles: &[T]) -> Result<(), Error> {
+    for tile in tiles {
+        write.write_all(&(tile.index() as u32).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Reads `expected.len()` tiles and tells if they are the expected ones.
+fn read_tiles_match<R: Read, T: Tile>(read: &mut R, expected: &[T]) -> Result<bool, Error> {
+    let mut matching = true;
+    for tile in expected {
+        matching &= read_u32(read)? as usize == tile.index();
+    }
+    Ok(matching)
+}
+
+impl<T: Tile> PatternDatabase<T> {
+    pub fn with_groups(expected: &Board<T>, groups: &[Vec<T>]) -> Result<Self, Error> {
+        Self::with_groups_until(expected, groups, None)
+    }
+
+    /// Gives up with `Error::Timeout` once `deadline` has passed.
+    pub fn with_groups_until(expected: &Board<T>, groups: &[Vec<T>], deadline: Option<Instant>) -> Result<Self, Error> {
+        let cells = expected.data.len();
+
+        let mut seen = vec![false; cells];
+        for &tile in groups.iter().flatten() {
+            let tile = tile.index();
+            if tile == 0 || tile >= cells || seen[tile] {
+                return Err(Error::InvalidGroups)
+            }
+            seen[tile] = true;
+        }
+
+        for group in groups {
+            if states_count(cells, group.len() + 1).is_none_or(|n| n > MAX_STATES) {
+                return Err(Error::GroupTooLarge)
+            }
+        }
+
+        let patterns = groups.iter().map(|group| Ok(Pattern {
+            tiles: group.clone().into_boxed_slice(),
+            costs: generate(expected, group, deadline)?,
+        })).collect::<Result<_, Error>>()?;
+
+        Ok(Self { expected: expected.clone(), patterns })
+    }
+
+    pub fn groups(&self) -> Vec<Vec<T>> {
+        self.patterns.iter().map(|p| p.tiles.to_vec()).collect()
+    }
+
+    pub fn save<W: Write>(&self, mut write: W) -> Result<(), Error> {
+        write.write_all(MAGIC)?;
+        write.write_all(&[VERSION])?;
+        write.write_all(&(self.expected.width as u32).to_le_bytes())?;
+        write.write_all(&(self.expected.height as u32).to_le_bytes())?;
+        write_tiles(&mut write, &self.expected.data)?;
+        write.write_all(&(self.patterns.len() as u32).to_le_bytes())?;
+        for pattern in &self.patterns {
+            write.write_all(&(pattern.tiles.len() as u32).to_le_bytes())?;
+            write_tiles(&mut write, &pattern.tiles)?;
+            write.write_all(&pattern.costs)?;
+        }
+        write.flush()?;
+        Ok(())
+    }
+
+    /// Reads a database generated for this exact goal and these groups.
+    pub fn load<R: Read>(mut read: R, expected: &Board<T>, groups: &[Vec<T>]) -> Result<Self, Error> {
+        let cells = expected.data.len();
+
+        let mut header = [0; 5];
+        read.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(Error::CorruptedDatabase)
+        }
+
+        let width = read_u32(&mut read)? as usize;
+        let height = read_u32(&mut read)? as usize;
+        if width != expected.width || height != expected.height {
+            return Err(Error::StaleDatabase)
+        }
+
+        if !read_tiles_match(&mut read, &expected.data)? {
+            return Err(Error::StaleDatabase)
+        }
+
+        let count = read_u32(&mut read)? as usize;
+        if count != groups.len() {
+            return Err(Error::StaleDatabase)
+        }
+
+        let mut patterns = Vec::with_capacity(count);
+        for group in groups {
+            let len = read_u32(&mut read)? as usize;
+            if len != group.len() {
+                return Err(Error::StaleDatabase)
+            }
+
+            if !read_tiles_match(&mut read, group)? {
+                return Err(Error::StaleDatabase)
+            }
+
+            let table_len = states_count(cells, len).ok_or(Error::CorruptedDatabase)?;
+            let mut costs = vec![0; table_len];
+            read.read_exact(&mut costs)?;
+
+            patterns.push(Pattern { tiles: group.clone().into_boxed_slice(), costs: costs.into_boxed_slice() });
+        }
+
+        Ok(Self { expected: expected.clone(), patterns })
+    }
+
+    pub fn save_to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.save(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load_from_path<P: AsRef<Path>>(path: P, expected: &Board<T>, groups: &[Vec<T>]) -> Result<Self, Error> {
+        Self::load(BufReader::new(File::open(path)?), expected, groups)
+    }
+}
+
+impl<T: Tile> Heuristic<T> for PatternDatabase<T> {
+    fn new(expected: &Board<T>) -> Self {
+        Self::with_groups(expected, &default_groups(expected)).expect("invalid default groups")
+    }
+}
+
+impl<T: Tile> DistanceFn<T> for PatternDatabase<T> {
+    fn name(&self) -> &'static str {
+        "pattern_database"
+    }
+
+    fn distance(&self, current: &Board<T>) -> usize {
+        let cells = current.data.len();
+        let mut positions = vec![0; cells];
+        for (i, &tile) in current.data.iter().enumerate() {
+            positions[tile.index()] = i;
+        }
+
+        self.patterns.iter().map(|pattern| {
+            let index = pattern.tiles.iter().rev().fold(0, |index, t| index * cells + positions[t.index()]);
+            pattern.costs[index] as usize
+        }).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristic::Manhattan;
+
+    fn expected() -> Board {
+        Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3)
+    }
+
+    fn groups() -> Vec<Vec<u8>> {
+        vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]]
+    }
+
+    #[test]
+    fn goal_is_zero() {
+        let expected = expected();
+        let database = PatternDatabase::with_groups(&expected, &groups()).unwrap();
+
+        assert_eq!(database.distance(&expected), 0)
+    }
+
+    #[test]
+    fn dominates_manhattan() {
+        let expected = expected();
+        let board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let database = PatternDatabase::with_groups(&expected, &groups()).unwrap();
+        let manhattan = Manhattan::new(&expected);
+
+        assert!(database.distance(&board) >= manhattan.distance(&board))
+    }
+
+    #[test]
+    fn rectangle() {
+        let expected: Board = Board::snail_with_size(4, 2);
+        let board = Board::with_size(vec![7, 1, 2, 3, 0, 6, 5, 4].into_boxed_slice(), 4, 2);
+        let database = PatternDatabase::with_groups(&expected, &[vec![1, 2, 3], vec![4, 5, 6, 7]]).unwrap();
+        let manhattan = Manhattan::new(&expected);
+
+        assert_eq!(database.distance(&expected), 0);
+        assert!(database.distance(&board) >= manhattan.distance(&board));
+    }
+
+    #[test]
+    fn invalid_groups() {
+        let expected = expected();
+
+        assert!(PatternDatabase::with_groups(&expected, &[vec![1, 2], vec![2, 3]]).is_err());
+        assert!(PatternDatabase::with_groups(&expected, &[vec![0, 1]]).is_err());
+        assert!(PatternDatabase::with_groups(&expected, &[vec![9]]).is_err());
+    }
+
+    #[test]
+    fn group_too_large() {
+        let expected: Board = Board::snail(4);
+        assert_eq!(default_groups(&expected).iter().map(Vec::len).collect::<Vec<_>>(), [6, 6, 3]);
+
+        let seven: Vec<u8> = (1..=7).collect();
+        match PatternDatabase::with_groups(&expected, &[seven]) {
+            Err(Error::GroupTooLarge) => (),
+            _ => panic!("a 7 tile group of the 4x4 is beyond MAX_STATES"),
+        }
+    }
+
+    #[test]
+    fn timeout() {
+        let large: Board = Board::snail(4);
+
+        match PatternDatabase::with_groups_until(&large, &[vec![1, 2, 3, 4, 5]], Some(Instant::now())) {
+            Err(Error::Timeout) => (),
+            _ => panic!("a generation past its deadline must give up"),
+        }
+        assert!(PatternDatabase::with_groups_until(&expected(), &groups(), Some(Instant::now())).is_ok());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let expected = expected();
+        let board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let database = PatternDatabase::with_groups(&expected, &groups()).unwrap();
+
+        let mut bytes = Vec::new();
+        database.save(&mut bytes).unwrap();
+        let loaded = PatternDatabase::load(&bytes[..], &expected, &groups()).unwrap();
+
+        assert_eq!(loaded.distance(&board), database.distance(&board));
+
+        // the tile width is not part of the file
+        let wide: Board<u16> = expected.convert().unwrap();
+        let wide_groups: Vec<Vec<u16>> = groups().iter().map(|g| g.iter().map(|&t| t.into()).collect()).collect();
+        let loaded = PatternDatabase::load(&bytes[..], &wide, &wide_groups).unwrap();
+
+        assert_eq!(loaded.distance(&board.convert().unwrap()), database.distance(&board))
+    }
+
+    #[test]
+    fn stale_database() {
+        let expected = expected();
+        let database = PatternDatabase::with_groups(&expected, &groups()).unwrap();
+
+        let mut bytes = Vec::new();
+        database.save(&mut bytes).unwrap();
+
+        let other_goal = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let other_groups = vec![vec![1, 2, 3], vec![4, 5, 6, 7, 8]];
+
+        match PatternDatabase::load(&bytes[..], &other_goal, &groups()) {
+            Err(Error::StaleDatabase) => (),
+            _ => panic!("a database for another goal must be rejected"),
+        }
+        match PatternDatabase::load(&bytes[..], &expected, &other_groups) {
+            Err(Error::StaleDatabase) => (),
+            _ => panic!("a database for other groups must be rejected"),
+        }
+    }
+}
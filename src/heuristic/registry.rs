@@ -0,0 +1,162 @@
+use std::str::FromStr;
+use std::{error, fmt};
+
+use crate::heuristic::{Heuristic, DistanceFn};
+use crate::heuristic::{Manhattan, Dijkstra, Euclidean, MissPlaced, OutOfRaw};
+use crate::heuristic::{LinearConflict, PatternDatabase, WalkingDistance};
+use crate::heuristic::{pattern_database, walking_distance};
+use crate::board::{Board, Tile};
+
+/// Every heuristic shipped with the crate, to pick one at runtime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HeuristicKind {
+    Manhattan,
+    Dijkstra,
+    Euclidean,
+    MissPlaced,
+    OutOfRaw,
+    LinearConflict,
+    PatternDatabase,
+    WalkingDistance,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownHeuristic(pub String);
+
+impl fmt::Display for UnknownHeuristic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown heuristic {:?}, expected one of {}", self.0, HeuristicKind::NAMES.join(", "))
+    }
+}
+
+impl error::Error for UnknownHeuristic { }
+
+/// Table of a heuristic which can't be built for a goal.
+#[derive(Debug)]
+pub enum BuildError {
+    PatternDatabase(pattern_database::Error),
+    WalkingDistance(walking_distance::Error),
+}
+
+impl From<pattern_database::Error> for BuildError {
+    fn from(error: pattern_database::Error) -> BuildError {
+        BuildError::PatternDatabase(error)
+    }
+}
+
+impl From<walking_distance::Error> for BuildError {
+    fn from(error: walking_distance::Error) -> BuildError {
+        BuildError::WalkingDistance(error)
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::PatternDatabase(error) => error.fmt(f),
+            BuildError::WalkingDistance(error) => error.fmt(f),
+        }
+    }
+}
+
+impl error::Error for BuildError { }
+
+impl HeuristicKind {
+    pub const ALL: &'static [HeuristicKind] = &[
+        HeuristicKind::Manhattan,
+        HeuristicKind::Dijkstra,
+        HeuristicKind::Euclidean,
+        HeuristicKind::MissPlaced,
+        HeuristicKind::OutOfRaw,
+        HeuristicKind::LinearConflict,
+        HeuristicKind::PatternDatabase,
+        HeuristicKind::WalkingDistance,
+    ];
+
+    /// Names of `ALL`, in the same order.
+    pub const NAMES: &'static [&'static str] = &[
+        "manhattan",
+        "dijkstra",
+        "euclidean",
+        "miss_placed",
+        "out_of_raw",
+        "linear_conflict",
+        "pattern_database",
+        "walking_distance",
+    ];
+
+    pub fn name(self) -> &'static str {
+        let index = HeuristicKind::ALL.iter().position(|&kind| kind == self).unwrap();
+        HeuristicKind::NAMES[index]
+    }
+
+    pub fn build<T: Tile>(self, expected: &Board<T>) -> Result<Box<dyn DistanceFn<T>>, BuildError> {
+        Ok(match self {
+            HeuristicKind::Manhattan => Box::new(Manhattan::new(expected)),
+            HeuristicKind::Dijkstra => Box::new(Dijkstra::new(expected)),
+            HeuristicKind::Euclidean => Box::new(Euclidean::new(expected)),
+            HeuristicKind::MissPlaced => Box::new(MissPlaced::new(expected)),
+            HeuristicKind::OutOfRaw => Box::new(OutOfRaw::new(expected)),
+            HeuristicKind::LinearConflict => Box::new(LinearConflict::new(expected)),
+            HeuristicKind::PatternDatabase =>
+                Box::new(PatternDatabase::with_groups(expected, &pattern_database::default_groups(expected))?),
+            HeuristicKind::WalkingDistance => Box::new(WalkingDistance::try_new(expected)?),
+        })
+    }
+}
+
+impl fmt::Display for HeuristicKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HeuristicKind {
+    type Err = UnknownHeuristic;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        HeuristicKind::NAMES.iter()
+            .position(|&name| name == string)
+            .map(|index| HeuristicKind::ALL[index])
+            .ok_or_else(|| UnknownHeuristic(string.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for &kind in HeuristicKind::ALL {
+            assert_eq!(kind.to_string().parse(), Ok(kind));
+        }
+    }
+
+    #[test]
+    fn built_names_match() {
+        let expected: Board = Board::snail(3);
+        for &kind in HeuristicKind::ALL {
+            assert_eq!(kind.build(&expected).unwrap().name(), kind.name());
+        }
+
+        let expected: Board<u16> = Board::snail(3);
+        for &kind in HeuristicKind::ALL {
+            assert_eq!(kind.build(&expected).unwrap().name(), kind.name());
+        }
+    }
+
+    #[test]
+    fn build_error() {
+        let expected: Board = Board::snail(6);
+        match HeuristicKind::WalkingDistance.build(&expected) {
+            Err(BuildError::WalkingDistance(_)) => (),
+            _ => panic!("the walking distance tables of a 6x6 don't fit"),
+        }
+    }
+
+    #[test]
+    fn unknown_name() {
+        assert_eq!("manhatan".parse::<HeuristicKind>(), Err(UnknownHeuristic("manhatan".to_string())));
+    }
+}
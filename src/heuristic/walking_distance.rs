@@ -0,0 +1,367 @@
+use std::sync::Arc;
+use std::{error, fmt};
+
+use crate::heuristic::{Heuristic, DistanceFn};
+use crate::board::{Board, Tile};
+
+/// Upper bound on the number of layouts of one axis, the 5x5 has 65650495.
+const MAX_LAYOUTS: usize = 1 << 27;
+
+/// Upper bound on the number of line codes of one axis, see `Layouts`.
+const MAX_CODES: usize = 1 << 16;
+
+const NO_NODE: u32 = u32::MAX;
+const UNKNOWN: u8 = u8::MAX;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Board with too many layouts to build its tables.
+    UnsupportedSize { width: usize, height: usize },
+    /// Board of another size than the goal, with other tiles or out of its reach.
+    UnknownLayout,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnsupportedSize { width, height } =>
+                write!(f, "walking distance tables are too large for a {}x{} board", width, height),
+            Error::UnknownLayout => write!(f, "board out of reach of the walking distance goal"),
+        }
+    }
+}
+
+impl error::Error for Error { }
+
+struct Node {
+    start: usize,
+    end: usize,
+    count: usize,
+}
+
+/// Dense ranking of the layouts of one axis. A layout counts the tiles of
+/// every goal line in every line, the blank being a goal line of its own, so
+/// every line holds `line_len` tiles. A line is written as a code whose digit
+/// `g` is its count of goal line `g`, the blank digit last.
+///
+/// A node stands for the lines left to fill with the tiles left, given as a
+/// code too. Its choices are the codes of its first line, with the number of
+/// layouts of the smaller choices as offset, so a layout is ranked by adding
+/// the offsets of its lines.
+struct Layouts {
+    lines: usize,
+    base: usize,
+    radix: Box<[usize]>,
+    /// Code of the tiles of every goal line.
+    full: usize,
+    /// Node of every code of tiles left, by line.
+    node_of: Box<[Box<[u32]>]>,
+    nodes: Vec<Node>,
+    /// Sorted by code within a node, `(code, offset)`.
+    choices: Vec<(usize, usize)>,
+    len: usize,
+}
+
+impl Layouts {
+    /// `blank_line` is the goal line of the blank, `None` when there are too many layouts.
+    fn new(lines: usize, line_len: usize, blank_line: usize) -> Option<Self> {
+        let base = line_len + 1;
+        let codes = base.checked_pow(lines as u32)?.checked_mul(2).filter(|&codes| codes <= MAX_CODES)?;
+        let radix: Box<[usize]> = (0..=lines).map(|g| base.pow(g as u32)).collect();
+        let full = (0..lines).map(|g| (line_len - (g == blank_line) as usize) * radix[g]).sum::<usize>() + radix[lines];
+
+        let digits: Vec<Vec<usize>> = (0..codes).map(|code| (0..=lines).map(|g| code / radix[g] % base).collect()).collect();
+        let line_codes: Vec<usize> = (0..codes).filter(|&code| digits[code].iter().sum::<usize>() == line_len).collect();
+
+        let mut node_of = vec![vec![NO_NODE; codes].into_boxed_slice(); lines + 1].into_boxed_slice();
+        let mut nodes = vec![Node { start: 0, end: 0, count: 1 }];
+        let mut choices = Vec::new();
+        node_of[lines][0] = 0;
+
+        // from the last line, the tiles left are those of the lines to fill
+        for line in (0..lines).rev() {
+            for left in 0..codes {
+                if digits[left].iter().sum::<usize>() != (lines - line) * line_len
+                    || digits[left].iter().zip(&digits[full]).any(|(left, full)| left > full)
+                {
+                    continue
+                }
+
+                let start = choices.len();
+                let mut count = 0usize;
+                for &code in &line_codes {
+                    if digits[code].iter().zip(&digits[left]).any(|(code, left)| code > left) {
+                        continue
+                    }
+                    let next = node_of[line + 1][left - code];
+                    if next != NO_NODE {
+                        choices.push((code, count));
+                        count = count.saturating_add(nodes[next as usize].count);
+                    }
+                }
+                if count > 0 {
+                    node_of[line][left] = nodes.len() as u32;
+                    nodes.push(Node { start, end: choices.len(), count });
+                }
+            }
+        }
+
+        let len = match node_of[0][full] {
+            NO_NODE => return None,
+            top => nodes[top as usize].count,
+        };
+        if len > MAX_LAYOUTS {
+            return None
+        }
+        Some(Self { lines, base, radix, full, node_of, nodes, choices, len })
+    }
+
+    fn digit(&self, code: usize, goal_line: usize) -> usize {
+        code / self.radix[goal_line] % self.base
+    }
+
+    /// Offset of the line `code` when `left` holds the tiles of this line and the following ones.
+    fn offset(&self, line: usize, left: usize, code: usize) -> Option<usize> {
+        let node = *self.node_of[line].get(left)?;
+        let node = self.nodes.get(node as usize)?;
+        let choices = &self.choices[node.start..node.end];
+        let choice = choices.binary_search_by_key(&code, |&(code, _)| code).ok()?;
+        Some(choices[choice].1)
+    }
+
+    fn rank(&self, codes: &[usize]) -> Option<usize> {
+        let mut left = self.full;
+        let mut index = 0;
+        for (line, &code) in codes.iter().enumerate() {
+            index += self.offset(line, left, code)?;
+            left -= code;
+        }
+        Some(index)
+    }
+
+    /// Fills the line codes of the layout `index`, with the tiles left and the offset of every line.
+    fn unrank(&self, mut index: usize, codes: &mut [usize], lefts: &mut [usize], offsets: &mut [usize]) {
+        let mut left = self.full;
+        for line in 0..self.lines {
+            let node = &self.nodes[self.node_of[line][left] as usize];
+            let choices = &self.choices[node.start..node.end];
+            let (code, offset) = choices[choices.partition_point(|&(_, offset)| offset <= index) - 1];
+
+            codes[line] = code;
+            lefts[line] = left;
+            offsets[line] = offset;
+            index -= offset;
+            left -= code;
+        }
+    }
+}
+
+/// Exact number of moves of every layout of one axis, from the goal layout
+/// where every tile is in its goal line.
+struct Table {
+    layouts: Layouts,
+    costs: Box<[u8]>,
+}
+
+impl Table {
+    fn new(lines: usize, line_len: usize, blank_line: usize) -> Option<Self> {
+        let layouts = Layouts::new(lines, line_len, blank_line)?;
+        let blank = layouts.radix[lines];
+        let goal: Vec<usize> = (0..lines)
+            .map(|line| (line_len - (line == blank_line) as usize) * layouts.radix[line] + (line == blank_line) as usize * blank)
+            .collect();
+
+        let mut costs = vec![UNKNOWN; layouts.len];
+        let goal = layouts.rank(&goal).unwrap();
+        costs[goal] = 0;
+
+        // breadth first search by cost layers, a move only changes the lines of the blank
+        // and of the moved tile so the offsets of the other lines are kept
+        let mut frontier = vec![goal as u32];
+        let (mut codes, mut lefts, mut offsets) = (vec![0; lines], vec![0; lines], vec![0; lines]);
+        let mut cost = 0u8;
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+
+            for &index in &frontier {
+                let index = index as usize;
+                layouts.unrank(index, &mut codes, &mut lefts, &mut offsets);
+                let zero = codes.iter().position(|&code| code >= blank).unwrap();
+
+                let neighbours = [zero.checked_sub(1), Some(zero + 1).filter(|&l| l < lines)];
+                for &line in neighbours.iter().flatten() {
+                    let first = zero.min(line);
+                    let others = index - offsets[first] - offsets[first + 1];
+
+                    for goal_line in 0..lines {
+                        if layouts.digit(codes[line], goal_line) == 0 {
+                            continue
+                        }
+                        let tile = layouts.radix[goal_line];
+                        let (zero_code, line_code) = (codes[zero] + tile - blank, codes[line] - tile + blank);
+                        let (first_code, second_code) = if zero < line { (zero_code, line_code) } else { (line_code, zero_code) };
+
+                        let left = lefts[first];
+                        let child = others
+                            + layouts.offset(first, left, first_code).unwrap()
+                            + layouts.offset(first + 1, left - first_code, second_code).unwrap();
+                        if costs[child] == UNKNOWN {
+                            costs[child] = cost + 1;
+                            next.push(child as u32);
+                        }
+                    }
+                }
+            }
+
+            frontier = next;
+            cost += 1;
+        }
+
+        Some(Self { layouts, costs: costs.into_boxed_slice() })
+    }
+}
+
+/// Walking distance along the rows or the columns.
+struct Axis {
+    goal_lines: Box<[u8]>,
+    table: Arc<Table>,
+}
+
+impl Axis {
+    fn distance<T: Tile>(&self, current: &Board<T>, line_of: impl Fn(usize) -> usize) -> Result<usize, Error> {
+        let layouts = &self.table.layouts;
+        let mut codes = vec![0; layouts.lines];
+        for (i, &tile) in current.data.iter().enumerate() {
+            let goal_line = *self.goal_lines.get(tile.index()).ok_or(Error::UnknownLayout)?;
+            codes[line_of(i)] += layouts.radix[goal_line as usize];
+        }
+
+        match layouts.rank(&codes).map(|index| self.table.costs[index]) {
+            Some(cost) if cost != UNKNOWN => Ok(cost as usize),
+            _ => Err(Error::UnknownLayout),
+        }
+    }
+}
+
+/// Takahashi's walking distance, the sum of the vertical and horizontal
+/// walking distances is admissible.
+pub struct WalkingDistance {
+    width: usize,
+    len: usize,
+    rows: Axis,
+    columns: Axis,
+}
+
+impl WalkingDistance {
+    /// Fails on boards with too many layouts, the 5x5 is the largest square.
+    pub fn try_new<T: Tile>(expected: &Board<T>) -> Result<Self, Error> {
+        let (width, height) = (expected.width, expected.height);
+        let unsupported = Error::UnsupportedSize { width, height };
+        if width > u8::MAX as usize || height > u8::MAX as usize {
+            return Err(unsupported)
+        }
+
+        // the blank is the goal line after the last one
+        let mut row_lines = vec![0; expected.data.len()];
+        let mut column_lines = vec![0; expected.data.len()];
+        for (i, &tile) in expected.data.iter().enumerate() {
+            row_lines[tile.index()] = (i / width) as u8;
+            column_lines[tile.index()] = (i % width) as u8;
+        }
+        let zero = expected.zero();
+        let (zero_row, zero_column) = (zero / width, zero % width);
+        row_lines[0] = height as u8;
+        column_lines[0] = width as u8;
+
+        let rows = Arc::new(Table::new(height, width, zero_row).ok_or(unsupported)?);
+        // a square with the blank on its diagonal has the same table on both axes
+        let columns = if (width, zero_column) == (height, zero_row) {
+            rows.clone()
+        } else {
+            Arc::new(Table::new(width, height, zero_column).ok_or(unsupported)?)
+        };
+
+        Ok(Self {
+            width,
+            len: expected.data.len(),
+            rows: Axis { goal_lines: row_lines.into_boxed_slice(), table: rows },
+            columns: Axis { goal_lines: column_lines.into_boxed_slice(), table: columns },
+        })
+    }
+
+    /// Fails on boards which don't have the size and tiles of the goal.
+    pub fn try_distance<T: Tile>(&self, current: &Board<T>) -> Result<usize, Error> {
+        let width = self.width;
+        if current.width != width || current.data.len() != self.len {
+            return Err(Error::UnknownLayout)
+        }
+        Ok(self.rows.distance(current, |i| i / width)? + self.columns.distance(current, |i| i % width)?)
+    }
+}
+
+/// Panics on the boards rejected by `try_new`.
+impl<T: Tile> Heuristic<T> for WalkingDistance {
+    fn new(expected: &Board<T>) -> Self {
+        Self::try_new(expected).unwrap_or_else(|error| panic!("{}", error))
+    }
+}
+
+/// Panics on the boards rejected by `try_distance`.
+impl<T: Tile> DistanceFn<T> for WalkingDistance {
+    fn name(&self) -> &'static str {
+        "walking_distance"
+    }
+
+    fn distance(&self, current: &Board<T>) -> usize {
+        self.try_distance(current).unwrap_or_else(|error| panic!("{}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristic::Manhattan;
+
+    #[test]
+    fn goal_is_zero() {
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let walking_distance = WalkingDistance::new(&expected);
+
+        assert_eq!(walking_distance.distance(&expected), 0)
+    }
+
+    #[test]
+    fn single_move() {
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 0, 3, 8, 2, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let walking_distance = WalkingDistance::new(&expected);
+
+        assert_eq!(walking_distance.distance(&board), 1)
+    }
+
+    #[test]
+    fn reversed_row_beats_manhattan() {
+        let expected: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0].into_boxed_slice(), 4);
+        let board: Board = Board::new(vec![2, 1, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0].into_boxed_slice(), 4);
+        let walking_distance = WalkingDistance::new(&expected);
+        let manhattan = Manhattan::new(&expected);
+
+        assert!(walking_distance.distance(&board) > manhattan.distance(&board))
+    }
+
+    #[test]
+    fn unsupported_size() {
+        for &(width, height) in &[(6, 6), (8, 2), (2, 9)] {
+            let expected: Board = Board::snail_with_size(width, height);
+            assert_eq!(WalkingDistance::try_new(&expected).err(), Some(Error::UnsupportedSize { width, height }));
+        }
+    }
+
+    #[test]
+    fn unknown_layout() {
+        let walking_distance = WalkingDistance::new(&Board::<u8>::snail(3));
+
+        assert_eq!(walking_distance.try_distance(&Board::<u8>::snail(4)), Err(Error::UnknownLayout));
+        assert_eq!(walking_distance.try_distance(&Board::<u8>::snail_with_size(3, 2)), Err(Error::UnknownLayout));
+    }
+}
@@ -1,10 +1,32 @@
 mod solver;
-mod heuristic;
+pub mod heuristic;
 mod board;
 mod state;
 mod tile_move;
+mod parser;
+mod generator;
+mod solve_result;
+mod solve_options;
+mod evaluation;
+mod bidirectional;
+mod packed_board;
+mod verify;
+mod move_sequence;
+mod report;
+mod comparison;
 
-pub use crate::board::{Board, Tile};
-pub use crate::solver::Solver;
-pub use crate::heuristic::Heuristic;
-pub use crate::heuristic::{Manhattan, Dijkstra, Euclidean, MissPlaced, OutOfRaw};
\ No newline at end of file
+pub use crate::board::{Board, Tile, IllegalMove};
+pub use crate::packed_board::PackedBoard;
+pub use crate::parser::{ParseError, ParseErrorKind, Puzzles};
+pub use crate::solver::{Solver, Error, Limit, is_solvable};
+pub use crate::solve_options::SolveOptions;
+pub use crate::evaluation::{Evaluation, Weight, InvalidEvaluation};
+pub use crate::solve_result::{SolveResult, SolveStats};
+pub use crate::generator::Generator;
+pub use crate::tile_move::{Move, InvalidMove};
+pub use crate::move_sequence::{MoveSequence, InvalidMoves};
+pub use crate::verify::{verify, VerifyError};
+pub use crate::report::{Report, Summary, Failure, FailureKind, Format, UnknownFormat};
+pub use crate::comparison::Comparison;
+pub use crate::heuristic::{Heuristic, DistanceFn, HeuristicKind};
+pub use crate::heuristic::{Manhattan, Dijkstra, Euclidean, MissPlaced, OutOfRaw, LinearConflict, PatternDatabase, WalkingDistance};
\ No newline at end of file
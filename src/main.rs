@@ -1,189 +1,939 @@
-use std::fs::File;
-use std::io::{self, Read, BufRead, BufReader};
+use std::{env, fmt, fs};
+use std::ffi::OsString;
 use std::num::ParseIntError;
-use std::path::PathBuf;
-use std::{fmt, process};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use structopt::StructOpt;
 
-use n_puzzle::{Board, Solver, Tile};
-use n_puzzle::{Manhattan, Dijkstra, Euclidean, MissPlaced, OutOfRaw};
+use n_puzzle::{Board, Solver, SolveOptions, SolveResult, Evaluation, Tile, ParseError, Puzzles, Generator, MoveSequence};
+use n_puzzle::{Report, Summary, Comparison, Failure, FailureKind, Format, InvalidMoves, VerifyError};
+use n_puzzle::heuristic::pattern_database;
+use n_puzzle::heuristic::check::Coverage;
+use n_puzzle::{DistanceFn, HeuristicKind, PatternDatabase};
+use n_puzzle::heuristic::BuildError;
+
+const SOLVE_AFTER_HELP: &str = "The heuristic used to be the positional argument after EXPECTED, it is now given with --heuristic.
+n-puzzle INPUT EXPECTED HEURISTIC, with or without the solve command, is still accepted with a warning.
+
+EXIT CODES:
+    0    solved
+    1    invalid command line
+    2    invalid or unmatching puzzle files
+    3    unsolvable puzzle
+    4    search limit reached
+    5    internal error";
+
+const CHECK_AFTER_HELP: &str = "EXIT CODES:
+    0    every heuristic is admissible
+    1    invalid command line
+    2    invalid goal or pattern groups
+    4    heuristic too large to build for this board
+    5    internal error
+    6    a heuristic overestimates the distance to the goal";
+
+/// Exit code of a check which found an overestimating heuristic, the failure classes use 1 to 5.
+const INADMISSIBLE_EXIT_CODE: i32 = 6;
 
-#[derive(Debug)]
-enum Error {
-    PuzzleInvalidTiles,
-    PuzzleMissingSize,
-    PuzzleInvalidNumberOfTiles,
-    PuzzleInvalidNumber(ParseIntError),
-    IoError(io::Error),
+#[derive(Debug, StructOpt)]
+#[structopt(name = "n-puzzle", about = "A* algorithm to solve npuzzles")]
+enum Opt {
+    /// Solve a npuzzle
+    #[structopt(name = "solve", raw(after_help = "SOLVE_AFTER_HELP"))]
+    Solve(SolveOpt),
+
+    /// Generate a solvable npuzzle in the input file format
+    #[structopt(name = "generate")]
+    Generate(GenerateOpt),
+
+    /// Check that a sequence of moves solves a npuzzle
+    #[structopt(name = "verify")]
+    Verify(VerifyOpt),
+
+    /// Solve every puzzle file of a directory on a thread pool and write a summary table,
+    /// every puzzle times out after 60 seconds, building its heuristic included, unless --timeout is given
+    #[structopt(name = "bench")]
+    Bench(BenchOpt),
+
+    /// Solve puzzles with every heuristic and compare their expansions, branching factors and solution lengths,
+    /// every search times out after 60 seconds, building its heuristic included, unless --timeout is given
+    #[structopt(name = "compare")]
+    Compare(CompareOpt),
+
+    /// Check that heuristics never overestimate the distance to the goal and differ by at most one
+    /// between neighbour states, exits with 6 when one of them overestimates
+    #[structopt(name = "check", raw(after_help = "CHECK_AFTER_HELP"))]
+    Check(CheckOpt),
+}
+
+#[derive(Debug, StructOpt)]
+struct SolveOpt {
+    /// Input file which contains the npuzzle to solve, - reads the standard input
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+
+    /// Input file which contains the npuzzle expected solution, same as --goal FILE
+    #[structopt(parse(from_os_str), conflicts_with = "goal")]
+    expected: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    search: SearchOpt,
+
+    /// Heuristic used to solve npuzzle
+    #[structopt(long = "heuristic", default_value = "manhattan", raw(possible_values = "HeuristicKind::NAMES"))]
+    heuristic: HeuristicKind,
+
+    /// Heuristic given after the expected file, deprecated for --heuristic
+    #[structopt(raw(possible_values = "HeuristicKind::NAMES", hidden = "true"))]
+    positional_heuristic: Option<HeuristicKind>,
+
+    /// Moves notation [letters, run-length, tiles], tiles adds the number of the tile moved at every step
+    #[structopt(long = "notation", default_value = "letters")]
+    notation: Notation,
+
+    /// Output format [text, json, csv], failures are reported in the same format with a non-zero exit code
+    #[structopt(long = "format", default_value = "text")]
+    format: Format,
+
+    /// Solve every puzzle of the input, separated by blank lines or --- lines, one record per puzzle
+    #[structopt(long = "batch")]
+    batch: bool,
+}
+
+/// Goal, algorithm and limits shared by the commands which solve puzzles.
+#[derive(Debug, StructOpt)]
+struct SearchOpt {
+    /// Expected solution [snail, row-major, FILE]
+    #[structopt(long = "goal", default_value = "snail")]
+    goal: Goal,
+
+    /// Ordering of the open set [uniform-cost, astar, greedy, weighted:W] where W is a weight such as 1.5 or 3/2, with at most 3 decimals and terms up to 1000000
+    #[structopt(long = "evaluation", default_value = "astar")]
+    evaluation: Evaluation,
+
+    /// Use iterative deepening A*, slower but memory stays linear in the solution depth
+    #[structopt(long = "ida", conflicts_with = "evaluation")]
+    ida: bool,
+
+    /// Search from the start and the goal at the same time with A*
+    #[structopt(long = "bidirectional", conflicts_with = "evaluation", conflicts_with = "ida")]
+    bidirectional: bool,
+
+    #[structopt(flatten)]
+    database: DatabaseOpt,
+
+    /// Give up after expanding this number of states
+    #[structopt(long = "max-expanded")]
+    max_expanded: Option<usize>,
+
+    /// Give up after this number of seconds, building the heuristic included
+    #[structopt(long = "timeout", parse(try_from_str = "parse_seconds"))]
+    timeout: Option<Duration>,
+
+    /// Give up when the open and closed sets hold more than this number of states
+    #[structopt(long = "max-memory")]
+    max_memory: Option<usize>,
+}
+
+/// Pattern database options, shared by the commands which build heuristics.
+#[derive(Debug, StructOpt)]
+struct DatabaseOpt {
+    /// Pattern database file, loaded if it matches the goal and groups, generated and written otherwise
+    #[structopt(long = "pdb-cache", parse(from_os_str))]
+    pdb_cache: Option<PathBuf>,
+
+    /// Pattern database groups, tiles separated by commas and groups by slashes (e.g. 1,2,3/4,5,6),
+    /// at most 6 tiles a group on a 4x4
+    #[structopt(long = "pdb-groups", parse(try_from_str = "parse_groups"))]
+    pdb_groups: Option<Vec<Vec<u32>>>,
+}
+
+impl SearchOpt {
+    fn algorithm(&self) -> String {
+        if self.ida {
+            "ida".to_string()
+        } else if self.bidirectional {
+            "bidirectional".to_string()
+        } else {
+            self.evaluation.to_string()
+        }
+    }
+
+    fn options(&self) -> SolveOptions {
+        SolveOptions {
+            evaluation: self.evaluation,
+            max_expanded: self.max_expanded,
+            timeout: self.timeout,
+            max_memory: self.max_memory,
+            cancel: None,
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct GenerateOpt {
+    /// Number of tiles on a line
+    #[structopt(long = "size", default_value = "3", parse(try_from_str = "parse_side"))]
+    size: usize,
+
+    /// Number of columns, the size when missing
+    #[structopt(long = "width", parse(try_from_str = "parse_side"))]
+    width: Option<usize>,
+
+    /// Number of rows, the size when missing
+    #[structopt(long = "height", parse(try_from_str = "parse_side"))]
+    height: Option<usize>,
+
+    /// Seed of the generator, a random one is picked and written in the output when missing
+    #[structopt(long = "seed")]
+    seed: Option<u64>,
+
+    /// Play this number of random moves from the goal instead of shuffling the tiles
+    #[structopt(long = "moves")]
+    moves: Option<usize>,
+
+    /// Goal the puzzle is generated from [snail, row-major, FILE]
+    #[structopt(long = "goal", default_value = "snail")]
+    goal: Goal,
+
+    /// Number of puzzles, separated by --- lines, each one generated with the next seed
+    #[structopt(long = "count", default_value = "1")]
+    count: u64,
+
+    /// Output file, the standard output when missing
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+struct VerifyOpt {
+    /// Input file which contains the npuzzle, - reads the standard input
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+
+    /// Moves to play, letters among U, D, L and R optionally followed by a repeat count (e.g. UDLR or R3D2)
+    moves: String,
+
+    /// Expected solution [snail, row-major, FILE]
+    #[structopt(long = "goal", default_value = "snail")]
+    goal: Goal,
+
+    /// Moves notation [letters, run-length, tiles], tiles are comma separated numbers of the tile moved (e.g. 5,8,7)
+    #[structopt(long = "notation", default_value = "letters")]
+    notation: Notation,
+}
+
+#[derive(Debug, StructOpt)]
+struct BenchOpt {
+    /// Directory which contains one npuzzle per file
+    #[structopt(parse(from_os_str))]
+    directory: PathBuf,
+
+    #[structopt(flatten)]
+    search: SearchOpt,
+
+    /// Heuristic used to solve npuzzle
+    #[structopt(long = "heuristic", default_value = "manhattan", raw(possible_values = "HeuristicKind::NAMES"))]
+    heuristic: HeuristicKind,
+
+    /// Number of puzzles solved at once, the number of CPUs when missing
+    #[structopt(short = "j", long = "jobs")]
+    jobs: Option<usize>,
 }
 
-impl From<io::Error> for Error {
-    fn from(error: io::Error) -> Error {
-        Error::IoError(error)
+#[derive(Debug, StructOpt)]
+struct CompareOpt {
+    /// Files which contain npuzzles separated by blank lines or --- lines, directories of such files or - for the standard input
+    #[structopt(parse(from_os_str), raw(required = "true"))]
+    inputs: Vec<PathBuf>,
+
+    #[structopt(flatten)]
+    search: SearchOpt,
+
+    /// Heuristic to compare, repeat it for several, all of them when missing
+    #[structopt(long = "heuristic", raw(possible_values = "HeuristicKind::NAMES", number_of_values = "1"))]
+    heuristics: Vec<HeuristicKind>,
+}
+
+#[derive(Debug, StructOpt)]
+struct CheckOpt {
+    /// Number of tiles on a line
+    #[structopt(long = "size", default_value = "3", parse(try_from_str = "parse_side"))]
+    size: usize,
+
+    /// Number of columns, the size when missing
+    #[structopt(long = "width", parse(try_from_str = "parse_side"))]
+    width: Option<usize>,
+
+    /// Number of rows, the size when missing
+    #[structopt(long = "height", parse(try_from_str = "parse_side"))]
+    height: Option<usize>,
+
+    /// Goal the distances are measured to [snail, row-major, FILE]
+    #[structopt(long = "goal", default_value = "snail")]
+    goal: Goal,
+
+    #[structopt(flatten)]
+    database: DatabaseOpt,
+
+    /// Heuristic to check, repeat it for several, all of them when missing
+    #[structopt(long = "heuristic", raw(possible_values = "HeuristicKind::NAMES", number_of_values = "1"))]
+    heuristics: Vec<HeuristicKind>,
+
+    /// Enumerate the states within this number of moves of the goal, every state up to 9 tiles when missing
+    #[structopt(long = "depth")]
+    depth: Option<usize>,
+
+    /// Random states whose neighbours are checked past the depth, when the states are not all enumerated
+    #[structopt(long = "samples", default_value = "1000")]
+    samples: usize,
+
+    /// Seed of the random states
+    #[structopt(long = "seed", default_value = "0")]
+    seed: u64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Notation {
+    Letters,
+    RunLength,
+    Tiles,
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "letters" => Ok(Notation::Letters),
+            "run-length" => Ok(Notation::RunLength),
+            "tiles" => Ok(Notation::Tiles),
+            notation => Err(format!("unknown notation {:?}", notation)),
+        }
     }
 }
 
-impl From<ParseIntError> for Error {
-    fn from(error: ParseIntError) -> Error {
-        Error::PuzzleInvalidNumber(error)
+#[derive(Debug)]
+enum Goal {
+    Snail,
+    RowMajor,
+    File(PathBuf),
+}
+
+impl FromStr for Goal {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "snail" => Ok(Goal::Snail),
+            "row-major" => Ok(Goal::RowMajor),
+            path if Path::new(path).is_file() => Ok(Goal::File(PathBuf::from(path))),
+            goal => Err(format!("unknown goal {:?}, expected snail, row-major or a puzzle file", goal)),
+        }
     }
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl Goal {
+    fn board<T: Tile>(&self, width: usize, height: usize) -> Result<Board<T>, ParseError> {
         match self {
-            Error::PuzzleInvalidTiles => write!(f, "invalid tiles"),
-            Error::PuzzleMissingSize => write!(f, "missing puzzle size"),
-            Error::PuzzleInvalidNumberOfTiles => write!(f, "invalid number of tiles"),
-            Error::PuzzleInvalidNumber(error) => write!(f, "invalid tile number; {}", error),
-            Error::IoError(error) => write!(f, "io error; {}", error),
+            Goal::Snail => Ok(Board::snail_with_size(width, height)),
+            Goal::RowMajor => Ok(Board::row_major_with_size(width, height)),
+            Goal::File(path) => Board::from_path(path),
         }
     }
 }
 
-impl std::error::Error for Error { }
+/// Longest board side, the cells of a square this large still fit `u32` tiles.
+const MAX_SIDE: usize = 65535;
 
-fn is_board_valid(numbers: &[Tile]) -> bool {
-    let sort_dedup_numbers = {
-        let mut numbers = numbers.to_vec();
-        numbers.sort_unstable();
-        numbers.dedup();
-        numbers
-    };
+fn parse_side(string: &str) -> Result<usize, String> {
+    match usize::from_str(string) {
+        Ok(side) if (1..=MAX_SIDE).contains(&side) => Ok(side),
+        _ => Err(format!("expected a number of tiles from 1 to {}", MAX_SIDE)),
+    }
+}
+
+fn parse_seconds(string: &str) -> Result<Duration, String> {
+    let seconds = f64::from_str(string).map_err(|error| error.to_string())?;
+    Duration::try_from_secs_f64(seconds).map_err(|error| error.to_string())
+}
 
-    if sort_dedup_numbers.len() != numbers.len() {
-        return false
+fn parse_groups(string: &str) -> Result<Vec<Vec<u32>>, ParseIntError> {
+    string.split('/')
+        .map(|group| group.split(',').map(|tile| u32::from_str(tile.trim())).collect())
+        .collect()
+}
+
+fn pattern_groups<T: Tile>(opt: &DatabaseOpt, expected: &Board<T>) -> Result<Vec<Vec<T>>, pattern_database::Error> {
+    match &opt.pdb_groups {
+        Some(groups) => {
+            // a tile too large for the board tile type is not on the board either
+            let tile = |&tile: &u32| Some(tile as usize).filter(|&tile| tile <= T::MAX).map(T::from_index);
+            groups.iter()
+                .map(|group| group.iter().map(tile).collect::<Option<Vec<T>>>())
+                .collect::<Option<Vec<_>>>()
+                .ok_or(pattern_database::Error::InvalidGroups)
+        },
+        None => Ok(pattern_database::default_groups(expected)),
     }
+}
+
+fn pattern_database<T: Tile>(opt: &DatabaseOpt, expected: &Board<T>, deadline: Option<Instant>)
+    -> Result<PatternDatabase<T>, pattern_database::Error>
+{
+    let groups = pattern_groups(opt, expected)?;
+    let path = match &opt.pdb_cache {
+        Some(path) => path,
+        None => return PatternDatabase::with_groups_until(expected, &groups, deadline),
+    };
 
-    if sort_dedup_numbers.get(0) != Some(&0) {
-        return false
+    // bench workers share the cache file
+    static CACHE: Mutex<()> = Mutex::new(());
+    let _cache = CACHE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    match PatternDatabase::load_from_path(path, expected, &groups) {
+        Ok(database) => Ok(database),
+        Err(pattern_database::Error::Io(_)) | Err(pattern_database::Error::StaleDatabase) => {
+            let database = PatternDatabase::with_groups_until(expected, &groups, deadline)?;
+            database.save_to_path(path)?;
+            Ok(database)
+        },
+        Err(error) => Err(error),
     }
+}
 
-    for array in sort_dedup_numbers.windows(2) {
-        if array[1] != array[0] + 1 {
-            return false
+fn generate(opt: GenerateOpt) -> Result<(), Box<dyn std::error::Error>> {
+    let goal: Board<u32> = opt.goal.board(opt.width.unwrap_or(opt.size), opt.height.unwrap_or(opt.size))?;
+    let seed = match opt.seed {
+        Some(seed) => seed,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+    };
+
+    let puzzles: Vec<String> = (0..opt.count).map(|i| {
+        let seed = seed.wrapping_add(i);
+        let mut generator = Generator::new(seed);
+        let board = match opt.moves {
+            Some(moves) => generator.random_walk(&goal, moves),
+            None => generator.shuffle(&goal),
+        };
+
+        match opt.moves {
+            Some(moves) => format!("# This puzzle is solvable\n# seed {}, {} random moves\n{}", seed, moves, board),
+            None => format!("# This puzzle is solvable\n# seed {}\n{}", seed, board),
         }
+    }).collect();
+    let content = puzzles.join("---\n");
+
+    match opt.output {
+        Some(path) => fs::write(path, content)?,
+        None => print!("{}", content),
     }
+    Ok(())
+}
+
+/// Failure already written in the requested format, only its exit code is left.
+#[derive(Debug)]
+struct Reported(FailureKind);
 
-    true
+impl fmt::Display for Reported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} failure reported", self.0.name())
+    }
 }
 
-fn no_comment(string: &str) -> Option<&str> {
-    string.split('#').next().filter(|s| !s.trim().is_empty())
+impl std::error::Error for Reported { }
+
+/// Check reports already written, one of the heuristics overestimates.
+#[derive(Debug)]
+struct Inadmissible;
+
+impl fmt::Display for Inadmissible {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("inadmissible heuristic reported")
+    }
 }
 
-fn read_board<R: Read>(read: R) -> Result<(Box<[Tile]>, usize), Error> {
-    let read = BufReader::new(read);
-    let mut lines = read.lines();
+impl std::error::Error for Inadmissible { }
 
-    // retrieve the puzzle size
-    let mut size = None;
-    for line in &mut lines {
-        let line = line?;
-        if let Some(number_part) = no_comment(&line) {
-            let trimmed = number_part.trim();
-            if !trimmed.is_empty() {
-                size = Some(usize::from_str(trimmed)?);
-                break;
-            }
-        }
+/// Name of the standard input in messages.
+const STDIN: &str = "<stdin>";
+
+/// Reads the puzzle in `path`, or from the standard input for `-`.
+fn read_input(path: &Path) -> Result<Board<u32>, ParseError> {
+    if path == Path::new("-") {
+        Board::from_reader(io::stdin()).map_err(|error| error.with_path(Path::new(STDIN)))
+    } else {
+        Board::from_path(path)
     }
+}
 
-    let size = match size {
-        Some(size) => size,
-        None => return Err(Error::PuzzleMissingSize),
+fn read_expected(opt: &SolveOpt, input: Board<u32>) -> Result<(Board<u32>, Board<u32>), ParseError> {
+    let expected = match &opt.expected {
+        Some(path) => Board::from_path(path)?,
+        None => opt.search.goal.board(input.width, input.height)?,
     };
+    Ok((input, expected))
+}
 
-    // retrieve the tiles numbers
-    let mut numbers = Vec::with_capacity(size * size);
-    for line in &mut lines {
-        let line = line?;
-        if let Some(tiles_part) = no_comment(&line) {
-            let prev_len = numbers.len();
+fn solve(mut opt: SolveOpt) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(heuristic) = opt.positional_heuristic {
+        eprintln!("warning: the heuristic positional argument is deprecated, use --heuristic {}", heuristic);
+        opt.heuristic = heuristic;
+    }
+    if opt.format == Format::Csv {
+        println!("{}", Report::CSV_HEADER);
+    }
+    if opt.batch {
+        return solve_batch(&opt)
+    }
 
-            for number_part in tiles_part.split_whitespace() {
-                let number = Tile::from_str(number_part)?;
-                numbers.push(number);
-            }
+    let report = solve_report(&opt.search, opt.heuristic, read_input(&opt.input).and_then(|input| read_expected(&opt, input)));
+    print_report(&opt, &report)?;
+    match &report.failure {
+        Some(failure) => Err(Box::new(Reported(failure.kind))),
+        None => Ok(()),
+    }
+}
 
-            if numbers.len() - prev_len != size {
-                return Err(Error::PuzzleInvalidNumberOfTiles)
-            }
-        }
+/// Solves every puzzle of the input stream, the first failure gives the exit code.
+fn solve_batch(opt: &SolveOpt) -> Result<(), Box<dyn std::error::Error>> {
+    let puzzles: Box<dyn Iterator<Item=Result<Board<u32>, ParseError>>> = if opt.input == Path::new("-") {
+        Box::new(Puzzles::new(io::stdin()).map(|input| input.map_err(|error| error.with_path(Path::new(STDIN)))))
+    } else {
+        Box::new(Puzzles::from_path(&opt.input)?)
+    };
+
+    let mut first_failure = None;
+    for (index, input) in puzzles.enumerate() {
+        let report = solve_report(&opt.search, opt.heuristic, input.and_then(|input| read_expected(opt, input))).with_puzzle(index + 1);
+        print_report(opt, &report)?;
+        first_failure = first_failure.or_else(|| report.failure.as_ref().map(|failure| failure.kind));
     }
 
-    if numbers.len() != size * size {
-        return Err(Error::PuzzleInvalidNumberOfTiles)
+    match first_failure {
+        Some(kind) => Err(Box::new(Reported(kind))),
+        None => Ok(()),
     }
+}
+
+fn solve_report(opt: &SearchOpt, heuristic: HeuristicKind, boards: Result<(Board<u32>, Board<u32>), ParseError>) -> Report {
+    let report = Report::new(heuristic.name(), opt.algorithm());
+    let (input, expected) = match boards {
+        Ok(boards) => boards,
+        Err(error) => return report.with_failure(Failure::new(FailureKind::InvalidInput, error)),
+    };
+
+    // the narrowest tile type keeps the closed set small
+    let largest = input.data.len().max(expected.data.len()) - 1;
+    let outcome = if largest <= <u8 as Tile>::MAX {
+        solve_tiles(opt, heuristic, input.convert::<u8>().unwrap(), expected.convert().unwrap())
+    } else if largest <= <u16 as Tile>::MAX {
+        solve_tiles(opt, heuristic, input.convert::<u16>().unwrap(), expected.convert().unwrap())
+    } else {
+        solve_tiles(opt, heuristic, input.clone(), expected.clone())
+    };
 
-    if !is_board_valid(&numbers) {
-        return Err(Error::PuzzleInvalidTiles)
+    let report = report.with_boards(&input, &expected);
+    match outcome {
+        Ok(outcome) => report.with_outcome(outcome),
+        Err(failure) => report.with_failure(failure),
     }
+}
 
-    Ok((numbers.into_boxed_slice(), size))
+/// Writes a report in the requested format, failures go to the standard error in text.
+fn print_report(opt: &SolveOpt, report: &Report) -> Result<(), Box<dyn std::error::Error>> {
+    match opt.format {
+        Format::Text => return print_text(opt, report),
+        #[cfg(feature = "json")]
+        Format::Json => println!("{}", report.to_json()),
+        #[cfg(not(feature = "json"))]
+        Format::Json => return Err("json output needs the json feature".into()),
+        Format::Csv => println!("{}", report.csv_record()),
+    }
+    Ok(())
 }
 
-#[derive(Debug, StructOpt)]
-#[structopt(name = "n-puzzle", about = "A* algorithm to solve npuzzles")]
-struct Opt {
-    /// Input file which contains the npuzzle to solve
-    #[structopt(parse(from_os_str))]
-    input: PathBuf,
+fn print_text(opt: &SolveOpt, report: &Report) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(puzzle) = report.puzzle {
+        println!("\npuzzle {}:", puzzle);
+    }
+    match (&report.result, &report.failure) {
+        (Some(result), _) if opt.notation == Notation::RunLength => println!("{:#}", result),
+        (Some(result), _) if opt.notation == Notation::Tiles => {
+            let input = report.input.as_ref().unwrap();
+            let tiles = MoveSequence(result.path.clone()).to_tiles(input)?;
+            let tiles: Vec<String> = tiles.iter().map(|tile| tile.to_string()).collect();
+            println!("{}\ntiles:\n{}", result, tiles.join(","));
+        },
+        (Some(result), _) => println!("{}", result),
+        (None, Some(failure)) => {
+            let input = if opt.input == Path::new("-") { Path::new(STDIN) } else { &opt.input };
+            match (failure.kind, report.puzzle) {
+                // parse errors already name their file
+                (FailureKind::InvalidInput, _) | (FailureKind::Internal, _) => eprintln!("{}", failure.message),
+                (_, Some(puzzle)) => eprintln!("{}, puzzle {}: {}", input.display(), puzzle, failure.message),
+                (_, None) => eprintln!("{}: {}", input.display(), failure.message),
+            }
+        },
+        (None, None) => unreachable!("a report has a result or a failure"),
+    }
+    Ok(())
+}
 
-    /// Input file which contains the npuzzle expected solution
-    #[structopt(parse(from_os_str))]
-    expected: PathBuf,
+/// Heuristic towards `goal`, a pattern database is built with the groups of `opt`
+/// and goes through its cache file only when `cached`, the file is keyed by one goal.
+/// Walking distance tables are not built above their size limit, pattern databases not past `deadline`.
+fn build_heuristic<T: Tile>(opt: &DatabaseOpt, kind: HeuristicKind, goal: &Board<T>, cached: bool, deadline: Option<Instant>)
+    -> Result<Box<dyn DistanceFn<T>>, Failure>
+{
+    let database = |error| database_failure(opt, error);
+    Ok(match kind {
+        HeuristicKind::PatternDatabase if cached => Box::new(pattern_database(opt, goal, deadline).map_err(database)?),
+        HeuristicKind::PatternDatabase => Box::new(pattern_groups(opt, goal)
+            .and_then(|groups| PatternDatabase::with_groups_until(goal, &groups, deadline))
+            .map_err(database)?),
+        kind => kind.build(goal).map_err(|error| match error {
+            BuildError::PatternDatabase(error) => database(error),
+            BuildError::WalkingDistance(error) => Failure::new(FailureKind::LimitReached, error),
+        })?,
+    })
+}
 
-    /// Heuristic used to solve npuzzle [manhattan, dijkstra, euclidean, miss_placed, out_of_raw]
-    heuristic: String,
+/// Groups come from the command line, the other errors from the cache file.
+fn database_failure(opt: &DatabaseOpt, error: pattern_database::Error) -> Failure {
+    match (error, &opt.pdb_cache) {
+        (error @ pattern_database::Error::InvalidGroups, _) | (error @ pattern_database::Error::GroupTooLarge, _) =>
+            Failure::new(FailureKind::InvalidInput, error),
+        (error @ pattern_database::Error::Timeout, _) => Failure::new(FailureKind::LimitReached, error),
+        (error, Some(path)) => Failure::new(FailureKind::Internal, format!("{}: {}", path.display(), error)),
+        (error, None) => Failure::new(FailureKind::Internal, error),
+    }
 }
 
-fn failable_main() -> Result<(), Box<std::error::Error>> {
-    let opt = Opt::from_args();
+fn solve_tiles<T: Tile>(opt: &SearchOpt, kind: HeuristicKind, input: Board<T>, expected: Board<T>)
+    -> Result<Result<SolveResult, n_puzzle::Error>, Failure>
+{
+    let solver = match Solver::new(input.clone(), expected.clone()) {
+        Ok(solver) => solver,
+        Err(error) => return Ok(Err(error)),
+    };
 
-    println!("Value for input: {:?}", opt.input);
-    println!("Value for expected: {:?}", opt.expected);
-    println!("Value for heuristic: {}", opt.heuristic);
+    // the search gets what is left of the timeout once the heuristics are built
+    let deadline = opt.timeout.map(|timeout| Instant::now() + timeout);
+    let heuristic = build_heuristic(&opt.database, kind, &expected, true, deadline)?;
+    let backward = if opt.bidirectional && !opt.ida {
+        Some(build_heuristic(&opt.database, kind, &input, false, deadline)?)
+    } else {
+        None
+    };
+    let options = SolveOptions {
+        timeout: deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())),
+        ..opt.options()
+    };
+    let solver = solver.with_options(options);
+
+    Ok(if opt.ida {
+        solver.solve_ida_with(&*heuristic)
+    } else if let Some(backward) = backward {
+        solver.solve_bidirectional_with(&*heuristic, &*backward)
+    } else {
+        solver.solve_with(&*heuristic)
+    })
+}
 
-    let input = {
-        let input_file = File::open(&opt.input)?;
-        let (input_numbers, input_size) = read_board(input_file)?;
-        Board::new(input_numbers, input_size)
+fn verify(opt: VerifyOpt) -> Result<(), Box<dyn std::error::Error>> {
+    let input = read_input(&opt.input)?;
+    let goal = opt.goal.board(input.width, input.height)?;
+    let moves = match opt.notation {
+        Notation::Tiles => MoveSequence::from_tiles(&input, &opt.moves)?,
+        _ => opt.moves.parse::<MoveSequence>()?,
     };
 
-    let expected = {
-        let expected_file = File::open(&opt.expected)?;
-        let (expected_numbers, expected_size) = read_board(expected_file)?;
-        Board::new(expected_numbers, expected_size)
+    n_puzzle::verify(&input, &goal, &moves.0)?;
+    println!("The {} moves solve the puzzle", moves.0.len());
+    Ok(())
+}
+
+/// Limit of every search of the bench and compare commands, a runaway instance must not stall the run.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn bench(mut opt: BenchOpt) -> Result<(), Box<dyn std::error::Error>> {
+    opt.search.timeout = opt.search.timeout.or(Some(DEFAULT_TIMEOUT));
+
+    let entries = match fs::read_dir(&opt.directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("{}: {}", opt.directory.display(), error);
+            return Err(Box::new(Reported(FailureKind::InvalidInput)))
+        },
     };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let jobs = opt.jobs.unwrap_or_else(|| thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+    let start = Instant::now();
+    let next = AtomicUsize::new(0);
+    let mut reports: Vec<(usize, Report)> = thread::scope(|scope| {
+        // every worker takes the next unsolved puzzle until none is left
+        let workers: Vec<_> = (0..jobs.clamp(1, paths.len().max(1))).map(|_| scope.spawn(|| {
+            let mut reports = Vec::new();
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let path = match paths.get(index) {
+                    Some(path) => path,
+                    None => return reports,
+                };
+                let boards = Board::from_path(path).and_then(|input| {
+                    let expected = opt.search.goal.board(input.width, input.height)?;
+                    Ok((input, expected))
+                });
+                reports.push((index, solve_report(&opt.search, opt.heuristic, boards)));
+            }
+        })).collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+    reports.sort_by_key(|&(index, _)| index);
+
+    let mut summary = Summary::new();
+    for (index, report) in reports {
+        let name = paths[index].file_name().unwrap_or(paths[index].as_os_str());
+        summary.push(name.to_string_lossy(), report);
+    }
+    summary.elapsed = start.elapsed();
+    print!("{}", summary);
+    Ok(())
+}
+
+/// Puzzle of a stream with its name, the file name numbered when the file holds several.
+type NamedPuzzle = (String, Result<Board<u32>, ParseError>);
+
+/// Puzzles of `path` named after their file, `-` reads the standard input and a directory its files.
+fn named_puzzles(path: &Path) -> Result<Vec<NamedPuzzle>, Box<dyn std::error::Error>> {
+    if path.is_dir() {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(path)? {
+            paths.push(entry?.path());
+        }
+        paths.sort();
+
+        let mut puzzles = Vec::new();
+        for path in paths.iter().filter(|path| path.is_file()) {
+            puzzles.extend(named_puzzles(path)?);
+        }
+        return Ok(puzzles)
+    }
+
+    let (name, puzzles): (_, Vec<_>) = if path == Path::new("-") {
+        let puzzles = Puzzles::new(io::stdin()).map(|input| input.map_err(|error| error.with_path(Path::new(STDIN))));
+        (STDIN.to_string(), puzzles.collect())
+    } else {
+        let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
+        match Puzzles::from_path(path) {
+            Ok(puzzles) => (name, puzzles.collect()),
+            Err(error) => (name, vec![Err(error)]),
+        }
+    };
+
+    let count = puzzles.len();
+    Ok(puzzles.into_iter().enumerate().map(|(i, puzzle)| match count {
+        1 => (name.clone(), puzzle),
+        _ => (format!("{} #{}", name, i + 1), puzzle),
+    }).collect())
+}
+
+fn compare(mut opt: CompareOpt) -> Result<(), Box<dyn std::error::Error>> {
+    opt.search.timeout = opt.search.timeout.or(Some(DEFAULT_TIMEOUT));
+    if opt.heuristics.is_empty() {
+        opt.heuristics = HeuristicKind::ALL.to_vec();
+    }
 
-    match Solver::new(input, expected) {
-        Ok(solver) => {
-            let result = match opt.heuristic.as_str() {
-                "manhattan" => solver.solve::<Manhattan>(),
-                "dijkstra" => solver.solve::<Dijkstra>(),
-                "euclidean" => solver.solve::<Euclidean>(),
-                "miss_placed" => solver.solve::<MissPlaced>(),
-                "out_of_raw" => solver.solve::<OutOfRaw>(),
-                _ => solver.solve::<Manhattan>(),
+    let mut comparison = Comparison::new();
+    let mut invalid = false;
+    for path in &opt.inputs {
+        for (name, input) in named_puzzles(path)? {
+            let boards = match input.and_then(|input| {
+                let expected = opt.search.goal.board(input.width, input.height)?;
+                Ok((input, expected))
+            }) {
+                Ok(boards) => boards,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    invalid = true;
+                    continue
+                },
             };
 
-            let (mem, time, moves) = result;
-            println!("memory complexity: {}", mem);
-            println!("time complexity: {}", time);
-            println!("moves count: {}", moves.len());
-            println!("moves:\r\n{:?}", moves);
-        },
-        Err(e) => println!("{}", e)
+            let reports = opt.heuristics.iter()
+                .map(|&kind| solve_report(&opt.search, kind, Ok(boards.clone())))
+                .collect();
+            comparison.push(name, reports);
+        }
+    }
+
+    print!("{}", comparison);
+    if invalid {
+        return Err(Box::new(Reported(FailureKind::InvalidInput)))
     }
     Ok(())
 }
 
+/// Cells of the largest board whose states are all enumerated, the 3x3 has 181440 of them.
+const EXHAUSTIVE_CELLS: usize = 9;
+
+/// Depth of the enumeration from the goal of the larger boards.
+const SAMPLED_DEPTH: usize = 12;
+
+fn check(mut opt: CheckOpt) -> Result<(), Box<dyn std::error::Error>> {
+    let goal: Board<u32> = opt.goal.board(opt.width.unwrap_or(opt.size), opt.height.unwrap_or(opt.size))?;
+    if opt.heuristics.is_empty() {
+        opt.heuristics = HeuristicKind::ALL.to_vec();
+    }
+    let coverage = match opt.depth {
+        None if goal.data.len() <= EXHAUSTIVE_CELLS => Coverage::exhaustive(),
+        depth => Coverage::sampled(depth.unwrap_or(SAMPLED_DEPTH), opt.samples, opt.seed),
+    };
+
+    let mut inadmissible = false;
+    let mut first_failure = None;
+    for (i, &kind) in opt.heuristics.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let heuristic = match build_heuristic(&opt.database, kind, &goal, true, None) {
+            Ok(heuristic) => heuristic,
+            Err(failure) => {
+                eprintln!("{}: {}", kind.name(), failure.message);
+                first_failure = first_failure.or(Some(failure.kind));
+                continue
+            },
+        };
+        let report = n_puzzle::heuristic::check::check(&*heuristic, &goal, coverage);
+        print!("{}", report);
+        inadmissible |= !report.is_admissible();
+    }
+
+    if inadmissible {
+        return Err(Box::new(Inadmissible))
+    }
+    match first_failure {
+        Some(kind) => Err(Box::new(Reported(kind))),
+        None => Ok(()),
+    }
+}
+
+/// Class of the errors which were not reported by the command.
+fn failure_kind(error: &(dyn std::error::Error + 'static)) -> FailureKind {
+    if let Some(error) = error.downcast_ref::<n_puzzle::Error>() {
+        Failure::from(error).kind
+    } else if error.is::<ParseError>() || error.is::<InvalidMoves>() || error.is::<VerifyError<u32>>() {
+        FailureKind::InvalidInput
+    } else {
+        FailureKind::Internal
+    }
+}
+
+/// Commands of `Opt`, and the help of the arguments parser.
+const COMMANDS: &[&str] = &["solve", "generate", "verify", "bench", "compare", "check", "help"];
+
+/// Arguments with `solve` inserted when they don't start with a command or a flag,
+/// the tool used to only solve and took its input first.
+fn with_default_command(mut args: Vec<OsString>) -> Vec<OsString> {
+    let is_command = |arg: &str| arg.starts_with('-') || COMMANDS.contains(&arg);
+    if args.get(1).is_some_and(|arg| !arg.to_str().is_some_and(is_command)) {
+        args.insert(1, "solve".into());
+    }
+    args
+}
+
+fn failable_main() -> Result<(), Box<dyn std::error::Error>> {
+    match Opt::from_iter(with_default_command(env::args_os().collect())) {
+        Opt::Solve(opt) => solve(opt),
+        Opt::Generate(opt) => generate(opt),
+        Opt::Verify(opt) => verify(opt),
+        Opt::Bench(opt) => bench(opt),
+        Opt::Compare(opt) => compare(opt),
+        Opt::Check(opt) => check(opt),
+    }
+}
+
 fn main() {
     if let Err(e) = failable_main() {
-        eprintln!("{}", e);
-        process::exit(1);
+        let code = match e.downcast_ref::<Reported>() {
+            Some(&Reported(kind)) => kind.exit_code(),
+            None if e.is::<Inadmissible>() => INADMISSIBLE_EXIT_CODE,
+            None => {
+                eprintln!("{}", e);
+                failure_kind(&*e).exit_code()
+            },
+        };
+        process::exit(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backward_pattern_database_groups() {
+        let goal: Board = Board::snail(3);
+        let start = Generator::new(1).shuffle(&goal);
+        let groups = vec![vec![1, 2, 3], vec![4, 5, 6, 7, 8]];
+        let opt = DatabaseOpt { pdb_cache: None, pdb_groups: Some(groups.clone()) };
+
+        let backward = build_heuristic(&opt, HeuristicKind::PatternDatabase, &start, false, None).unwrap();
+        let groups: Vec<Vec<u8>> = groups.iter().map(|group| group.iter().map(|&tile| tile as u8).collect()).collect();
+        let expected = PatternDatabase::with_groups(&start, &groups).unwrap();
+        let default = HeuristicKind::PatternDatabase.build(&start).unwrap();
+
+        let mut generator = Generator::new(2);
+        let boards: Vec<Board> = (0..20).map(|_| generator.shuffle(&start)).collect();
+        assert!(boards.iter().all(|board| backward.distance(board) == expected.distance(board)));
+        assert!(boards.iter().any(|board| backward.distance(board) != default.distance(board)));
+    }
+
+    #[test]
+    fn default_command() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        assert_eq!(with_default_command(args(&["n_puzzle", "in.txt", "goal.txt", "manhattan"])),
+            args(&["n_puzzle", "solve", "in.txt", "goal.txt", "manhattan"]));
+        assert_eq!(with_default_command(args(&["n_puzzle", "check", "--size", "3"])), args(&["n_puzzle", "check", "--size", "3"]));
+        assert_eq!(with_default_command(args(&["n_puzzle", "--help"])), args(&["n_puzzle", "--help"]));
+        assert_eq!(with_default_command(args(&["n_puzzle"])), args(&["n_puzzle"]));
+
+        match Opt::from_iter(with_default_command(args(&["n_puzzle", "in.txt", "goal.txt", "euclidean"]))) {
+            Opt::Solve(opt) => assert_eq!(opt.positional_heuristic, Some(HeuristicKind::Euclidean)),
+            opt => panic!("expected the solve command, not {:?}", opt),
+        }
+    }
+
+    #[test]
+    fn invalid_seconds() {
+        assert_eq!(parse_seconds("1.5"), Ok(Duration::from_millis(1500)));
+        assert!(parse_seconds("-1").is_err());
+        assert!(parse_seconds("NaN").is_err());
+        assert!(parse_seconds("1e30").is_err());
+    }
+
+    #[test]
+    fn walking_distance_limit() {
+        let opt = DatabaseOpt { pdb_cache: None, pdb_groups: None };
+        let goal: Board = Board::snail(6);
+        let failure = build_heuristic(&opt, HeuristicKind::WalkingDistance, &goal, true, None).err().unwrap();
+        assert_eq!(failure.kind, FailureKind::LimitReached);
+        assert!(build_heuristic(&opt, HeuristicKind::WalkingDistance, &Board::<u8>::snail(4), true, None).is_ok());
     }
 }
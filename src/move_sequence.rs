@@ -0,0 +1,162 @@
+use std::str::FromStr;
+use std::{error, fmt};
+
+use crate::board::{Board, Tile, IllegalMove, slides};
+use crate::tile_move::Move;
+
+/// Columns are one based, steps zero based like `IllegalMove::step`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidMoves {
+    UnknownLetter { column: usize, letter: char },
+    /// Repeat count which is zero or too large.
+    InvalidCount { column: usize },
+    InvalidTile { column: usize },
+    /// Tile which is not next to the zero when it should move.
+    NotAdjacent { step: usize, tile: usize },
+}
+
+impl fmt::Display for InvalidMoves {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidMoves::UnknownLetter { column, letter } =>
+                write!(f, "column {}: invalid move {:?}, expected U, D, L or R", column, letter),
+            InvalidMoves::InvalidCount { column } => write!(f, "column {}: invalid repeat count", column),
+            InvalidMoves::InvalidTile { column } => write!(f, "column {}: invalid tile number", column),
+            InvalidMoves::NotAdjacent { step, tile } =>
+                write!(f, "move {}: tile {} is not next to the zero", step + 1, tile),
+        }
+    }
+}
+
+impl error::Error for InvalidMoves { }
+
+/// Moves written with their letters, `RRRDD`, or run length compressed with
+/// the alternate flag `{:#}`, `R3D2`. Parsing accepts both forms.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MoveSequence(pub Vec<Move>);
+
+impl From<Vec<Move>> for MoveSequence {
+    fn from(moves: Vec<Move>) -> Self {
+        MoveSequence(moves)
+    }
+}
+
+impl MoveSequence {
+    /// Number of the tile which slides at every move, played from `start`.
+    pub fn to_tiles<T: Tile>(&self, start: &Board<T>) -> Result<Vec<T>, IllegalMove> {
+        let mut board = start.clone();
+        let mut tiles = Vec::with_capacity(self.0.len());
+        for (step, &move_) in self.0.iter().enumerate() {
+            let zero = board.zero();
+            board.apply(move_).map_err(|_| IllegalMove { step, move_ })?;
+            tiles.push(board.data[zero]);
+        }
+        Ok(tiles)
+    }
+
+    /// Reads comma separated tile numbers, e.g. `5,8,7`, as played from `start`.
+    pub fn from_tiles<T: Tile>(start: &Board<T>, string: &str) -> Result<Self, InvalidMoves> {
+        let mut board = start.clone();
+        let mut moves = Vec::new();
+        let mut column = 1;
+
+        for (step, word) in string.split(',').enumerate() {
+            let tile = usize::from_str(word.trim()).map_err(|_| InvalidMoves::InvalidTile { column })?;
+            column += word.chars().count() + 1;
+
+            let zero = board.zero();
+            let move_ = slides(zero, board.width, board.height).iter()
+                .find(|&&(_, cell)| cell.is_some_and(|cell| board.data[cell].index() == tile))
+                .map(|&(move_, _)| move_)
+                .ok_or(InvalidMoves::NotAdjacent { step, tile })?;
+            board.apply(move_).unwrap();
+            moves.push(move_);
+        }
+
+        Ok(MoveSequence(moves))
+    }
+}
+
+impl fmt::Display for MoveSequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !f.alternate() {
+            return self.0.iter().try_for_each(|move_| write!(f, "{}", move_))
+        }
+
+        let mut moves = self.0.iter().peekable();
+        while let Some(&move_) = moves.next() {
+            let mut count = 1;
+            while moves.next_if_eq(&&move_).is_some() {
+                count += 1;
+            }
+            match count {
+                1 => write!(f, "{}", move_)?,
+                count => write!(f, "{}{}", move_, count)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MoveSequence {
+    type Err = InvalidMoves;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut moves = Vec::new();
+        let mut chars = string.chars().zip(1..).filter(|(c, _)| !c.is_whitespace()).peekable();
+
+        while let Some((letter, column)) = chars.next() {
+            let move_ = Move::from_letter(letter).ok_or(InvalidMoves::UnknownLetter { column, letter })?;
+
+            let mut count: Option<usize> = None;
+            while let Some((digit, _)) = chars.next_if(|(c, _)| c.is_ascii_digit()) {
+                let digit = digit.to_digit(10).unwrap() as usize;
+                count = count.unwrap_or(0).checked_mul(10).and_then(|count| count.checked_add(digit));
+                if count.is_none() {
+                    return Err(InvalidMoves::InvalidCount { column: column + 1 })
+                }
+            }
+            match count {
+                Some(0) => return Err(InvalidMoves::InvalidCount { column: column + 1 }),
+                count => moves.extend(std::iter::repeat_n(move_, count.unwrap_or(1))),
+            }
+        }
+
+        Ok(MoveSequence(moves))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_and_run_length() {
+        let moves = MoveSequence(vec![Move::Right, Move::Right, Move::Right, Move::Down, Move::Down, Move::Left]);
+
+        assert_eq!(moves.to_string(), "RRRDDL");
+        assert_eq!(format!("{:#}", moves), "R3D2L");
+        assert_eq!("RRRDDL".parse(), Ok(moves.clone()));
+        assert_eq!("R3D2L".parse(), Ok(moves.clone()));
+        assert_eq!("r3 d2 l".parse(), Ok(moves));
+        assert_eq!("".parse(), Ok(MoveSequence::default()));
+    }
+
+    #[test]
+    fn invalid_letters() {
+        assert_eq!("UDX".parse::<MoveSequence>(), Err(InvalidMoves::UnknownLetter { column: 3, letter: 'X' }));
+        assert_eq!("3U".parse::<MoveSequence>(), Err(InvalidMoves::UnknownLetter { column: 1, letter: '3' }));
+        assert_eq!("U0".parse::<MoveSequence>(), Err(InvalidMoves::InvalidCount { column: 2 }));
+    }
+
+    #[test]
+    fn tiles() {
+        let start: Board = Board::row_major(3);
+        let moves = MoveSequence(vec![Move::Down, Move::Right, Move::Up]);
+
+        assert_eq!(moves.to_tiles(&start), Ok(vec![6, 5, 8]));
+        assert_eq!(MoveSequence::from_tiles(&start, "6, 5,8"), Ok(moves));
+        assert_eq!(MoveSequence::from_tiles(&start, "6,1"), Err(InvalidMoves::NotAdjacent { step: 1, tile: 1 }));
+        assert_eq!(MoveSequence::from_tiles(&start, "6,a"), Err(InvalidMoves::InvalidTile { column: 3 }));
+    }
+}
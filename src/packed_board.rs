@@ -0,0 +1,121 @@
+use std::hash::Hash;
+
+use crate::board::{Board, Tile, slides};
+use crate::tile_move::Move;
+
+/// Board of at most 16 tiles packed in a `u64`, 4 bits per tile in reading order.
+///
+/// The size is not part of it, as a closed set key it takes 8 bytes instead of a boxed slice.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PackedBoard(u64);
+
+impl PackedBoard {
+    pub const MAX_CELLS: usize = 16;
+
+    /// `None` if the board has more than `MAX_CELLS` tiles.
+    pub fn new<T: Tile>(board: &Board<T>) -> Option<Self> {
+        if board.data.len() > Self::MAX_CELLS {
+            return None
+        }
+        let bits = board.data.iter().rev().fold(0, |bits, tile| bits << 4 | tile.index() as u64);
+        Some(PackedBoard(bits))
+    }
+
+    pub fn tile(self, index: usize) -> usize {
+        (self.0 >> (4 * index) & 0xf) as usize
+    }
+
+    pub fn zero(self, width: usize, height: usize) -> usize {
+        (0..width * height).find(|&i| self.tile(i) == 0).unwrap()
+    }
+
+    pub fn unpack<T: Tile>(self, width: usize, height: usize) -> Board<T> {
+        let data = (0..width * height).map(|i| T::from_index(self.tile(i))).collect();
+        Board::with_size(data, width, height)
+    }
+
+    /// Packed board after the tile at `cell` slid into the zero at `zero`.
+    fn slide(self, zero: usize, cell: usize) -> Self {
+        let tile = self.0 >> (4 * cell) & 0xf;
+        PackedBoard(self.0 & !(0xf << (4 * cell)) | tile << (4 * zero))
+    }
+
+    /// Same children as `Board::children`, with the move which reached them.
+    pub fn children(self, width: usize, height: usize) -> impl Iterator<Item=(Move, PackedBoard)> {
+        let zero = self.zero(width, height);
+        IntoIterator::into_iter(slides(zero, width, height))
+            .filter_map(move |(move_, cell)| cell.map(|cell| (move_, self.slide(zero, cell))))
+    }
+}
+
+/// Board representation used in the open and closed sets of a search,
+/// heuristics still run on a scratch `Board` the key is decoded into.
+pub(crate) trait SearchKey<T: Tile>: Clone + Eq + Ord + Hash {
+    fn encode(board: &Board<T>) -> Self;
+    fn decode_into(&self, board: &mut Board<T>);
+    /// Key after the tile at `cell` slid into the zero at `zero`.
+    fn slide(&self, zero: usize, cell: usize) -> Self;
+}
+
+impl<T: Tile> SearchKey<T> for Box<[T]> {
+    fn encode(board: &Board<T>) -> Self {
+        board.data.clone()
+    }
+
+    fn decode_into(&self, board: &mut Board<T>) {
+        board.data.copy_from_slice(self);
+    }
+
+    fn slide(&self, zero: usize, cell: usize) -> Self {
+        let mut data = self.clone();
+        data.swap(zero, cell);
+        data
+    }
+}
+
+impl<T: Tile> SearchKey<T> for PackedBoard {
+    fn encode(board: &Board<T>) -> Self {
+        PackedBoard::new(board).expect("board too large to be packed")
+    }
+
+    fn decode_into(&self, board: &mut Board<T>) {
+        for (i, tile) in board.data.iter_mut().enumerate() {
+            *tile = T::from_index(self.tile(i));
+        }
+    }
+
+    fn slide(&self, zero: usize, cell: usize) -> Self {
+        PackedBoard::slide(*self, zero, cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let board: Board = Board::snail(4);
+        let packed = PackedBoard::new(&board).unwrap();
+
+        assert_eq!(packed.unpack::<u8>(4, 4), board);
+        assert_eq!(packed.zero(4, 4), board.zero());
+        assert!(PackedBoard::new(&Board::<u8>::snail(5)).is_none());
+    }
+
+    #[test]
+    fn children_match_board() {
+        for board in [Board::<u8>::snail(3), Board::snail(4), Board::row_major_with_size(4, 2)].iter() {
+            let packed = PackedBoard::new(board).unwrap();
+            let children: Vec<Board> = packed.children(board.width, board.height)
+                .map(|(move_, child)| {
+                    let child = child.unpack(board.width, board.height);
+                    assert_eq!(board.moved(move_).as_ref(), Some(&child));
+                    child
+                })
+                .collect();
+
+            assert_eq!(children, board.children());
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use std::fs::File;
+use std::io::{self, Read, BufRead, BufReader, Lines};
+use std::iter::Enumerate;
+use std::marker::PhantomData;
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{error, fmt};
+
+use crate::board::{Board, Tile};
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    InvalidTiles,
+    InvalidSize,
+    MissingSize,
+    InvalidNumberOfTiles,
+    InvalidNumber(ParseIntError),
+    Io(io::Error),
+}
+
+/// Parse error located at a one based line and column of the input.
+#[derive(Debug)]
+pub struct ParseError {
+    /// File the board was read from, `None` for other readers.
+    pub path: Option<PathBuf>,
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, kind: ParseErrorKind) -> Self {
+        Self { path: None, line, column, kind }
+    }
+
+    /// Names the file the input was read from in the message.
+    pub fn with_path(self, path: &Path) -> Self {
+        Self { path: Some(path.to_path_buf()), ..self }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::InvalidTiles => write!(f, "invalid tiles"),
+            ParseErrorKind::InvalidSize => write!(f, "invalid puzzle size"),
+            ParseErrorKind::MissingSize => write!(f, "missing puzzle size"),
+            ParseErrorKind::InvalidNumberOfTiles => write!(f, "invalid number of tiles"),
+            ParseErrorKind::InvalidNumber(error) => write!(f, "invalid tile number; {}", error),
+            ParseErrorKind::Io(error) => write!(f, "io error; {}", error),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}: ", path.display())?;
+        }
+        if self.line == 0 {
+            // not located, the input could not even be opened
+            return write!(f, "{}", self.kind)
+        }
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.kind {
+            ParseErrorKind::InvalidNumber(error) => Some(error),
+            ParseErrorKind::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+fn no_comment(string: &str) -> Option<&str> {
+    string.split('#').next().filter(|s| !s.trim().is_empty())
+}
+
+/// Whitespace separated words with their one based column.
+fn words(string: &str) -> impl Iterator<Item=(usize, &str)> {
+    string.split(char::is_whitespace)
+        .scan(1, |column, word| {
+            let start = *column;
+            *column += word.chars().count() + 1;
+            Some((start, word))
+        })
+        .filter(|(_, word)| !word.is_empty())
+}
+
+/// Index of the first tile which is duplicated or out of the board.
+fn first_invalid_tile<T: Tile>(numbers: &[T]) -> Option<usize> {
+    let mut seen = vec![false; numbers.len()];
+    numbers.iter().position(|&number| {
+        let number = number.index();
+        if number >= seen.len() || seen[number] {
+            return true
+        }
+        seen[number] = true;
+        false
+    })
+}
+
+/// Parses the lines of one puzzle, with their one based line number.
+fn parse_lines<T: Tile, I>(lines: I) -> Result<Board<T>, ParseError>
+    where I: IntoIterator<Item=(usize, io::Result<String>)>
+{
+    let mut lines = lines.into_iter();
+    let mut last_line = 0;
+
+    // retrieve the puzzle size, `N` for a square or `W H`
+    let mut size = None;
+    for (line_number, line) in &mut lines {
+        last_line = line_number;
+        let line = line.map_err(|e| ParseError::new(line_number, 1, ParseErrorKind::Io(e)))?;
+        if let Some(number_part) = no_comment(&line) {
+            let mut numbers = Vec::with_capacity(2);
+            for (column, word) in words(number_part) {
+                let number = usize::from_str(word)
+                    .map_err(|e| ParseError::new(line_number, column, ParseErrorKind::InvalidNumber(e)))?;
+                numbers.push(number);
+            }
+
+            size = match numbers[..] {
+                [size] => Some((size, size)),
+                [width, height] => Some((width, height)),
+                _ => None,
+            }.filter(|&(width, height)| width.checked_mul(height).is_some_and(|cells| cells > 0));
+            if size.is_none() {
+                return Err(ParseError::new(line_number, 1, ParseErrorKind::InvalidSize))
+            }
+            break;
+        }
+    }
+
+    let (width, height) = match size {
+        Some(size) => size,
+        None => return Err(ParseError::new(last_line + 1, 1, ParseErrorKind::MissingSize)),
+    };
+
+    // retrieve the tiles numbers, the size line alone doesn't say how much to allocate
+    let mut numbers = Vec::new();
+    let mut locations = Vec::new();
+    for (line_number, line) in &mut lines {
+        last_line = line_number;
+        let line = line.map_err(|e| ParseError::new(line_number, 1, ParseErrorKind::Io(e)))?;
+        if let Some(tiles_part) = no_comment(&line) {
+            let prev_len = numbers.len();
+
+            for (column, number_part) in words(tiles_part) {
+                let number = T::from_str(number_part)
+                    .map_err(|e| ParseError::new(line_number, column, ParseErrorKind::InvalidNumber(e)))?;
+                numbers.push(number);
+                locations.push((line_number, column));
+            }
+
+            if numbers.len() - prev_len != width {
+                return Err(ParseError::new(line_number, 1, ParseErrorKind::InvalidNumberOfTiles))
+            }
+        }
+    }
+
+    if numbers.len() != width * height {
+        return Err(ParseError::new(last_line + 1, 1, ParseErrorKind::InvalidNumberOfTiles))
+    }
+
+    if let Some(index) = first_invalid_tile(&numbers) {
+        let (line, column) = locations[index];
+        return Err(ParseError::new(line, column, ParseErrorKind::InvalidTiles))
+    }
+
+    Ok(Board::with_size(numbers.into_boxed_slice(), width, height))
+}
+
+impl<T: Tile> Board<T> {
+    /// Reads a puzzle in the comment aware format: a size line, `N` for a square
+    /// or `W H` for a rectangle, followed by the tiles lines.
+    pub fn from_reader<R: Read>(read: R) -> Result<Self, ParseError> {
+        parse_lines(BufReader::new(read).lines().enumerate().map(|(i, line)| (i + 1, line)))
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ParseError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| ParseError::new(0, 0, ParseErrorKind::Io(e)).with_path(path))?;
+        Board::from_reader(file).map_err(|error| error.with_path(path))
+    }
+}
+
+/// Puzzles of a stream in the `Board::from_reader` format, separated by blank lines
+/// or `---` lines. Errors are located in the whole stream and the next puzzle is
+/// still read after one, up to an io error which ends the stream.
+pub struct Puzzles<T: Tile, R: Read> {
+    lines: Enumerate<Lines<BufReader<R>>>,
+    /// Named in the errors when read from a file.
+    path: Option<PathBuf>,
+    ended: bool,
+    tile: PhantomData<T>,
+}
+
+impl<T: Tile, R: Read> Puzzles<T, R> {
+    pub fn new(read: R) -> Self {
+        Self { lines: BufReader::new(read).lines().enumerate(), path: None, ended: false, tile: PhantomData }
+    }
+
+    fn error(&self, error: ParseError) -> ParseError {
+        match &self.path {
+            Some(path) => error.with_path(path),
+            None => error,
+        }
+    }
+}
+
+impl<T: Tile> Puzzles<T, File> {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ParseError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| ParseError::new(0, 0, ParseErrorKind::Io(e)).with_path(path))?;
+        Ok(Self { path: Some(path.to_path_buf()), ..Self::new(file) })
+    }
+}
+
+impl<T: Tile, R: Read> Iterator for Puzzles<T, R> {
+    type Item = Result<Board<T>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut block = Vec::new();
+        let mut has_content = false;
+
+        while !self.ended {
+            let (line_number, line) = match self.lines.next() {
+                Some((i, line)) => (i + 1, line),
+                None => {
+                    self.ended = true;
+                    break
+                },
+            };
+            let line = match line {
+                Ok(line) => line,
+                Err(error) => {
+                    self.ended = true;
+                    return Some(Err(self.error(ParseError::new(line_number, 1, ParseErrorKind::Io(error)))))
+                },
+            };
+
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed == "---" {
+                if has_content {
+                    break
+                }
+                // only comments so far, they belong to no puzzle
+                block.clear();
+                continue
+            }
+            has_content |= no_comment(&line).is_some();
+            block.push((line_number, Ok(line)));
+        }
+
+        if has_content {
+            Some(parse_lines(block).map_err(|error| self.error(error)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Tile> FromStr for Board<T> {
+    type Err = ParseError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Board::from_reader(string.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn comments() {
+        let board: Board = "# this is a comment\n3\n3 2 6 #another comment\n1 4 0\n8 7 5\n".parse().unwrap();
+
+        assert_eq!(board, Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3))
+    }
+
+    #[test]
+    fn rectangle() {
+        let board: Board = "3 2\n1 2 3\n4 5 0\n".parse().unwrap();
+        assert_eq!(board, Board::row_major_with_size(3, 2));
+
+        assert!("3 2\n1 2 3\n4 5 0\n".parse::<Board<u32>>().is_ok());
+
+        assert!("3 2\n1 2\n3 4\n5 0\n".parse::<Board>().is_err());
+        assert!("3 2 1\n1 2 3\n4 5 0\n".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn oversized() {
+        for input in &["4294967296 4294967296\n1 0\n", "18446744073709551615 2\n1 0\n", "4294967296\n1 0\n"] {
+            let error = input.parse::<Board<u32>>().unwrap_err();
+            match error.kind {
+                ParseErrorKind::InvalidSize => assert_eq!((error.line, error.column), (1, 1)),
+                kind => panic!("unexpected error kind {:?}", kind),
+            }
+        }
+
+        let error = "100000 100000\n1 0\n".parse::<Board<u32>>().unwrap_err();
+        match error.kind {
+            ParseErrorKind::InvalidNumberOfTiles => (),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn error_location() {
+        let error = "3\n3 2 6\n1 4 a\n8 7 5\n".parse::<Board>().unwrap_err();
+        assert_eq!((error.line, error.column), (3, 5));
+
+        let error = "3\n3 2 6\n1 4 0\n8 7 6\n".parse::<Board>().unwrap_err();
+        assert_eq!((error.line, error.column), (4, 5));
+        match error.kind {
+            ParseErrorKind::InvalidTiles => (),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn error_path() {
+        let error = Board::<u8>::from_path("test_files/invalids/dup_teal").unwrap_err();
+        assert_eq!(error.path.as_deref(), Some(Path::new("test_files/invalids/dup_teal")));
+        assert!(error.to_string().starts_with(&format!("test_files/invalids/dup_teal: line {}, column ", error.line)));
+
+        let error = Board::<u8>::from_path("test_files/missing").unwrap_err();
+        assert!(error.to_string().starts_with("test_files/missing: io error; "));
+        assert!("3\n1 2\n".parse::<Board>().unwrap_err().path.is_none());
+    }
+
+    #[test]
+    fn stream() {
+        let stream = "# first\n3\n1 2 3\n8 0 4\n7 6 5\n\n\n2\n1 2\n3 0\n---\n# bad\n2\n1 2\n3 3\n---\n2 1\n0 1\n\n# end\n";
+        let puzzles: Vec<_> = Puzzles::<u8, _>::new(stream.as_bytes()).collect();
+
+        assert_eq!(puzzles.len(), 4);
+        assert_eq!(puzzles[0].as_ref().unwrap(), &Board::snail(3));
+        assert_eq!(puzzles[1].as_ref().unwrap(), &Board::row_major(2));
+        let error = puzzles[2].as_ref().unwrap_err();
+        assert_eq!((error.line, error.column), (15, 3));
+        assert_eq!(puzzles[3].as_ref().unwrap(), &Board::with_size(vec![0, 1].into_boxed_slice(), 2, 1));
+
+        assert_eq!(Puzzles::<u8, _>::new("# nothing\n\n---\n".as_bytes()).count(), 0);
+        let puzzles: Vec<_> = Puzzles::<u8, _>::from_path("test_files/valids/01").unwrap().collect();
+        assert_eq!(puzzles.len(), 1);
+        assert!(puzzles[0].is_ok());
+    }
+
+    #[test]
+    fn wide_tiles() {
+        let board: Board<u16> = Board::snail(17);
+        let error = board.to_string().parse::<Board<u8>>().unwrap_err();
+        match error.kind {
+            ParseErrorKind::InvalidNumber(_) => (),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+        assert_eq!(board.to_string().parse::<Board<u16>>().unwrap(), board);
+    }
+
+    #[test]
+    fn valid_files() {
+        for entry in fs::read_dir("test_files/valids").unwrap() {
+            let path = entry.unwrap().path();
+            assert!(Board::<u8>::from_path(&path).is_ok(), "{:?} must be valid", path);
+        }
+    }
+
+    #[test]
+    fn invalid_files() {
+        let names = [
+            "big_val_teal", "comment", "diff_col_line", "diff_line_col", "dup_teal", "empty",
+            "error_map1.txt", "error_map2.txt", "error_map3.txt", "error_map4.txt", "error_map5.txt",
+            "error_map6.txt", "error_map7.txt", "error_map8.txt", "error_map9.txt", "error_map10.txt",
+            "error_map11.txt", "error_map13.txt", "invalid_teal", "line_info", "no_line_info",
+            "no_zero_teal", "one_col",
+        ];
+        for name in names.iter() {
+            let path = Path::new("test_files/invalids").join(name);
+            assert!(Board::<u8>::from_path(&path).is_err(), "{:?} must be invalid", path);
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use std::str::FromStr;
+use std::fmt;
+use std::time::Duration;
+
+use crate::board::{Board, Tile};
+use crate::move_sequence::MoveSequence;
+use crate::solve_result::{SolveResult, SolveStats};
+use crate::solver::Error;
+
+/// Output format of the command line tool.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownFormat(pub String);
+
+impl fmt::Display for UnknownFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown format {:?}, expected text, json or csv", self.0)
+    }
+}
+
+impl std::error::Error for UnknownFormat { }
+
+impl FromStr for Format {
+    type Err = UnknownFormat;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            format => Err(UnknownFormat(format.to_string())),
+        }
+    }
+}
+
+/// Class of a failed solve.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "kebab-case"))]
+pub enum FailureKind {
+    InvalidInput,
+    UnmatchingSizes,
+    Unsolvable,
+    LimitReached,
+    Cancelled,
+    Internal,
+}
+
+impl FailureKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FailureKind::InvalidInput => "invalid-input",
+            FailureKind::UnmatchingSizes => "unmatching-sizes",
+            FailureKind::Unsolvable => "unsolvable",
+            FailureKind::LimitReached => "limit-reached",
+            FailureKind::Cancelled => "cancelled",
+            FailureKind::Internal => "internal",
+        }
+    }
+
+    /// Exit code of the command line tool for this failure, 1 is left to usage errors.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailureKind::InvalidInput | FailureKind::UnmatchingSizes => 2,
+            FailureKind::Unsolvable => 3,
+            FailureKind::LimitReached | FailureKind::Cancelled => 4,
+            FailureKind::Internal => 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Failure {
+    pub kind: FailureKind,
+    pub message: String,
+    /// Counters of a search which gave up.
+    pub stats: Option<SolveStats>,
+}
+
+impl Failure {
+    pub fn new(kind: FailureKind, message: impl fmt::Display) -> Self {
+        Self { kind, message: message.to_string(), stats: None }
+    }
+}
+
+impl From<&Error> for Failure {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::UnmatchingSizes => Failure::new(FailureKind::UnmatchingSizes, error),
+            Error::Unsolvable => Failure::new(FailureKind::Unsolvable, error),
+            Error::LimitReached { stats, .. } => Failure {
+                stats: Some(stats.clone()),
+                ..Failure::new(FailureKind::LimitReached, error)
+            },
+            Error::Cancelled => Failure::new(FailureKind::Cancelled, error),
+        }
+    }
+}
+
+/// Everything known about one solve, for the JSON and CSV outputs.
+///
+/// Boards are widened to `u32` tiles so a report doesn't depend on the tile type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Report {
+    /// One based position of the puzzle in a stream, `None` for a single puzzle.
+    pub puzzle: Option<usize>,
+    /// `None` if it could not be read.
+    pub input: Option<Board<u32>>,
+    pub goal: Option<Board<u32>>,
+    pub heuristic: &'static str,
+    /// `astar`, `ida`, `bidirectional` or another evaluation such as `weighted:3/2`.
+    pub algorithm: String,
+    /// `None` if the boards could not be compared.
+    pub solvable: Option<bool>,
+    pub result: Option<SolveResult>,
+    pub failure: Option<Failure>,
+}
+
+/// Quotes a CSV field if it holds a separator, a quote or a new line.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl Report {
+    pub const CSV_HEADER: &'static str = "puzzle,status,heuristic,algorithm,solvable,optimal,path_cost,\
+        nodes_expanded,nodes_generated,nodes_reopened,max_open_set,closed_set,tree_nodes,elapsed_ms,moves,message";
+
+    pub fn new(heuristic: &'static str, algorithm: impl Into<String>) -> Self {
+        Self {
+            puzzle: None,
+            input: None,
+            goal: None,
+            heuristic,
+            algorithm: algorithm.into(),
+            solvable: None,
+            result: None,
+            failure: None,
+        }
+    }
+
+    pub fn with_puzzle(self, puzzle: usize) -> Self {
+        Self { puzzle: Some(puzzle), ..self }
+    }
+
+    pub fn with_boards<T: Tile>(self, input: &Board<T>, goal: &Board<T>) -> Self {
+        Self { input: input.convert(), goal: goal.convert(), ..self }
+    }
+
+    /// Records the outcome of a solver, `solvable` is known from both.
+    pub fn with_outcome(self, outcome: Result<SolveResult, Error>) -> Self {
+        match outcome {
+            Ok(result) => Self { solvable: Some(true), result: Some(result), ..self },
+            Err(error) => {
+                let solvable = match error {
+                    Error::Unsolvable => Some(false),
+                    Error::UnmatchingSizes => None,
+                    _ => Some(true),
+                };
+                Self { solvable, failure: Some(Failure::from(&error)), ..self }
+            },
+        }
+    }
+
+    pub fn with_failure(self, failure: Failure) -> Self {
+        Self { failure: Some(failure), ..self }
+    }
+
+    /// `solved` or the failure kind.
+    pub fn status(&self) -> &'static str {
+        self.failure.as_ref().map_or("solved", |failure| failure.kind.name())
+    }
+
+    /// One line matching `CSV_HEADER`, fields which don't apply are left empty.
+    pub fn csv_record(&self) -> String {
+        let stats = self.result.as_ref().map(|result| &result.stats)
+            .or_else(|| self.failure.as_ref().and_then(|failure| failure.stats.as_ref()));
+        let stat = |field: fn(&SolveStats) -> usize| stats.map_or(String::new(), |stats| field(stats).to_string());
+
+        let fields = [
+            self.puzzle.map_or(String::new(), |puzzle| puzzle.to_string()),
+            self.status().to_string(),
+            self.heuristic.to_string(),
+            self.algorithm.clone(),
+            self.solvable.map_or(String::new(), |solvable| solvable.to_string()),
+            self.result.as_ref().map_or(String::new(), |result| result.optimal.to_string()),
+            self.result.as_ref().map_or(String::new(), |result| result.path_cost.to_string()),
+            stat(|stats| stats.nodes_expanded),
+            stat(|stats| stats.nodes_generated),
+            stat(|stats| stats.nodes_reopened),
+            stat(|stats| stats.max_open_set),
+            stat(|stats| stats.closed_set),
+            stat(|stats| stats.tree_nodes),
+            stats.map_or(String::new(), |stats| format!("{:.3}", stats.elapsed.as_secs_f64() * 1000.0)),
+            self.result.as_ref().map_or(String::new(), |result| MoveSequence(result.path.clone()).to_string()),
+            self.failure.as_ref().map_or(String::new(), |failure| failure.message.clone()),
+        ];
+
+        fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",")
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a report is always serializable")
+    }
+}
+
+/// Reports of many puzzles written as a table followed by aggregate statistics.
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    rows: Vec<(String, Report)>,
+    /// Wall clock time of the whole run, the solves may overlap.
+    pub elapsed: Duration,
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn mean(total: usize, count: usize) -> f64 {
+    total as f64 / count.max(1) as f64
+}
+
+impl Summary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the report of the puzzle named `name`, rows keep this order.
+    pub fn push(&mut self, name: impl Into<String>, report: Report) {
+        self.rows.push((name.into(), report));
+    }
+
+    pub fn reports(&self) -> impl Iterator<Item=&Report> {
+        self.rows.iter().map(|(_, report)| report)
+    }
+
+    /// Counters of a row, a search which gave up has them as well.
+    fn stats(report: &Report) -> Option<&SolveStats> {
+        report.result.as_ref().map(|result| &result.stats)
+            .or_else(|| report.failure.as_ref().and_then(|failure| failure.stats.as_ref()))
+    }
+}
+
+/// Memory is the number of search tree nodes held at the end of a search.
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name_width = self.rows.iter().map(|(name, _)| name.chars().count()).chain(Some(4)).max().unwrap();
+        writeln!(f, "{:<name_width$}  {:<16}  {:>6}  {:>10}  {:>10}  {:>12}",
+            "file", "status", "length", "expanded", "memory", "time", name_width = name_width)?;
+
+        for (name, report) in &self.rows {
+            let stats = Summary::stats(report);
+            let stat = |field: fn(&SolveStats) -> usize| stats.map_or("-".to_string(), |stats| field(stats).to_string());
+            writeln!(f, "{:<name_width$}  {:<16}  {:>6}  {:>10}  {:>10}  {:>12}",
+                name,
+                report.status(),
+                report.result.as_ref().map_or("-".to_string(), |result| result.path_cost.to_string()),
+                stat(|stats| stats.nodes_expanded),
+                stat(|stats| stats.tree_nodes),
+                stats.map_or("-".to_string(), |stats| milliseconds(stats.elapsed)),
+                name_width = name_width)?;
+        }
+
+        let solved: Vec<&SolveResult> = self.reports().filter_map(|report| report.result.as_ref()).collect();
+        let mut failures: Vec<(&'static str, usize)> = Vec::new();
+        for failure in self.reports().filter_map(|report| report.failure.as_ref()) {
+            match failures.iter_mut().find(|(name, _)| *name == failure.kind.name()) {
+                Some((_, count)) => *count += 1,
+                None => failures.push((failure.kind.name(), 1)),
+            }
+        }
+
+        writeln!(f)?;
+        write!(f, "solved {}/{}", solved.len(), self.rows.len())?;
+        for (name, count) in failures {
+            write!(f, ", {} {}", count, name)?;
+        }
+        writeln!(f)?;
+        if solved.is_empty() {
+            return writeln!(f, "wall clock: {}", milliseconds(self.elapsed))
+        }
+
+        let lengths = solved.iter().map(|result| result.path_cost);
+        let expanded = solved.iter().map(|result| result.stats.nodes_expanded);
+        let memory = solved.iter().map(|result| result.stats.tree_nodes);
+        let time: Duration = solved.iter().map(|result| result.stats.elapsed).sum();
+        writeln!(f, "length of the solved: mean {:.1}, max {}", mean(lengths.clone().sum(), solved.len()), lengths.max().unwrap())?;
+        writeln!(f, "expanded: total {}, mean {:.1}, max {}",
+            expanded.clone().sum::<usize>(), mean(expanded.clone().sum(), solved.len()), expanded.max().unwrap())?;
+        writeln!(f, "memory: mean {:.1}, max {}", mean(memory.clone().sum(), solved.len()), memory.max().unwrap())?;
+        writeln!(f, "time: total {}, mean {}, max {}, wall clock {}",
+            milliseconds(time),
+            milliseconds(time / solved.len() as u32),
+            milliseconds(solved.iter().map(|result| result.stats.elapsed).max().unwrap()),
+            milliseconds(self.elapsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+    use crate::heuristic::Manhattan;
+
+    #[test]
+    fn csv() {
+        let input: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let goal: Board = Board::new(vec![1, 0, 3, 8, 2, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let outcome = Solver::new(input.clone(), goal.clone()).unwrap().solve::<Manhattan>();
+        let report = Report::new("manhattan", "astar").with_boards(&input, &goal).with_outcome(outcome);
+
+        let record = report.csv_record();
+        assert_eq!(record.split(',').count(), Report::CSV_HEADER.split(',').count());
+        assert!(record.starts_with(",solved,manhattan,astar,true,true,1,"));
+        assert!(record.ends_with(",D,"));
+
+        let failure = Failure::new(FailureKind::InvalidInput, "line 2, column 3: invalid tiles");
+        let report = Report::new("manhattan", "astar").with_puzzle(2).with_failure(failure);
+        assert_eq!(report.csv_record(), "2,invalid-input,manhattan,astar,,,,,,,,,,,,\"line 2, column 3: invalid tiles\"");
+    }
+
+    #[test]
+    fn unsolvable() {
+        let report = Report::new("manhattan", "astar").with_outcome(Err(Error::Unsolvable));
+
+        assert_eq!(report.solvable, Some(false));
+        assert_eq!(report.status(), "unsolvable");
+    }
+
+    #[test]
+    fn summary() {
+        let goal: Board = Board::snail(3);
+        let mut summary = Summary::new();
+        for (name, data) in [("near", vec![1, 0, 3, 8, 2, 4, 7, 6, 5]), ("far", vec![0, 1, 3, 8, 2, 4, 7, 6, 5])].iter() {
+            let input = Board::new(data.clone().into_boxed_slice(), 3);
+            let outcome = Solver::new(input.clone(), goal.clone()).unwrap().solve::<Manhattan>();
+            summary.push(*name, Report::new("manhattan", "astar").with_boards(&input, &goal).with_outcome(outcome));
+        }
+        summary.push("swapped", Report::new("manhattan", "astar").with_outcome(Err(Error::Unsolvable)));
+
+        let table = summary.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("file     status"));
+        assert!(lines[1].starts_with("near     solved                 1"));
+        assert!(lines[3].starts_with("swapped  unsolvable             -           -           -             -"));
+        assert_eq!(lines[5], "solved 2/3, 1 unsolvable");
+        assert_eq!(lines[6], "length of the solved: mean 1.5, max 2");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let report = Report::new("manhattan", "ida").with_outcome(Err(Error::Unsolvable));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+
+        assert_eq!(json["algorithm"], "ida");
+        assert_eq!(json["solvable"], false);
+        assert_eq!(json["failure"]["kind"], "unsolvable");
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::solve_result::SolveStats;
+use crate::solver::{Error, Limit};
+use crate::evaluation::Evaluation;
+
+/// Search settings, A* without any limit by default.
+#[derive(Debug, Clone, Default)]
+pub struct SolveOptions {
+    /// Ordering of the open set, iterative deepening always uses `g + h`.
+    pub evaluation: Evaluation,
+    /// Maximum number of expanded states.
+    pub max_expanded: Option<usize>,
+    pub timeout: Option<Duration>,
+    /// Maximum number of states held in the open and closed sets together.
+    pub max_memory: Option<usize>,
+    /// Stops the search as soon as it is set, from another thread for example.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl SolveOptions {
+    /// Errors if a limit is exceeded, `memory` counts the states currently held.
+    pub(crate) fn check(&self, start: Instant, stats: &SolveStats, memory: usize) -> Result<(), Error> {
+        if self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Err(Error::Cancelled)
+        }
+
+        let limit = if self.max_expanded.is_some_and(|max| stats.nodes_expanded >= max) {
+            Limit::Expanded
+        } else if self.max_memory.is_some_and(|max| memory > max) {
+            Limit::Memory
+        } else if self.timeout.is_some_and(|timeout| start.elapsed() > timeout) {
+            Limit::Timeout
+        } else {
+            return Ok(())
+        };
+
+        let mut stats = stats.clone();
+        stats.elapsed = start.elapsed();
+        Err(Error::LimitReached { limit, stats })
+    }
+}
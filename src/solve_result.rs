@@ -0,0 +1,143 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::tile_move::Move;
+use crate::move_sequence::MoveSequence;
+
+/// Counters gathered while searching, also reported when a search gives up.
+///
+/// Iterative deepening keeps neither an open nor a closed set: `nodes_reopened`,
+/// `max_open_set` and `closed_set` stay 0 and `tree_nodes` is the current path.
+/// Bidirectional search adds up the counters of both sides.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SolveStats {
+    /// States whose children were generated.
+    pub nodes_expanded: usize,
+    /// Children pushed to the open set.
+    pub nodes_generated: usize,
+    /// Closed states pushed again after a cheaper path to them was found.
+    pub nodes_reopened: usize,
+    /// Largest number of states waiting in the open set at once.
+    pub max_open_set: usize,
+    /// States in the closed set when the search ended.
+    pub closed_set: usize,
+    /// Nodes of the search tree held in memory when the search ended.
+    pub tree_nodes: usize,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SolveResult {
+    pub path: Vec<Move>,
+    pub path_cost: usize,
+    pub heuristic: &'static str,
+    /// Whether `path` is guaranteed to be a shortest one.
+    pub optimal: bool,
+    pub stats: SolveStats,
+}
+
+impl SolveResult {
+    /// Branching factor `b` of a uniform tree of depth `path_cost` holding the generated
+    /// nodes, `N + 1 = 1 + b + b^2 + ... + b^d`. `None` for an empty path.
+    pub fn effective_branching_factor(&self) -> Option<f64> {
+        let depth = self.path_cost as i32;
+        if depth == 0 {
+            return None
+        }
+        let nodes = self.stats.nodes_generated.max(self.path_cost) as f64 + 1.0;
+        let tree = |b: f64| (0..=depth).map(|i| b.powi(i)).sum::<f64>();
+
+        let (mut low, mut high) = (1.0, nodes);
+        for _ in 0..64 {
+            let middle = (low + high) / 2.0;
+            if tree(middle) < nodes {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        Some((low + high) / 2.0)
+    }
+}
+
+/// The moves are written with their letters, run length compressed with the alternate flag `{:#}`.
+impl fmt::Display for SolveResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "heuristic: {}", self.heuristic)?;
+        writeln!(f, "optimal: {}", if self.optimal { "yes" } else { "not guaranteed" })?;
+        writeln!(f, "time complexity: {} nodes generated, {} expanded, {} reopened",
+            self.stats.nodes_generated, self.stats.nodes_expanded, self.stats.nodes_reopened)?;
+        writeln!(f, "memory complexity: {} tree nodes, {} max open set, {} closed set",
+            self.stats.tree_nodes, self.stats.max_open_set, self.stats.closed_set)?;
+        writeln!(f, "elapsed: {:?}", self.stats.elapsed)?;
+        writeln!(f, "moves count: {}", self.path_cost)?;
+        let moves = MoveSequence(self.path.clone());
+        if f.alternate() {
+            write!(f, "moves:\n{:#}", moves)
+        } else {
+            write!(f, "moves:\n{}", moves)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(path_cost: usize, nodes_generated: usize) -> SolveResult {
+        let stats = SolveStats { nodes_generated, ..SolveStats::default() };
+        SolveResult { path: vec![Move::Up; path_cost], path_cost, heuristic: "test", optimal: true, stats }
+    }
+
+    #[test]
+    fn effective_branching_factor() {
+        // 1 + 2 + 4 + 8 = 14 generated nodes and the root
+        let b = result(3, 14).effective_branching_factor().unwrap();
+        assert!((b - 2.0).abs() < 1e-9, "{}", b);
+
+        let b = result(5, 5).effective_branching_factor().unwrap();
+        assert!((b - 1.0).abs() < 1e-9, "{}", b);
+
+        assert_eq!(result(0, 0).effective_branching_factor(), None);
+    }
+    #[test]
+    fn display() {
+        let stats = SolveStats {
+            nodes_expanded: 6,
+            nodes_generated: 14,
+            nodes_reopened: 1,
+            max_open_set: 8,
+            closed_set: 6,
+            tree_nodes: 15,
+            elapsed: Duration::from_micros(1500),
+        };
+        let result = SolveResult { path: vec![Move::Right, Move::Right, Move::Down], path_cost: 3, heuristic: "manhattan", optimal: false, stats };
+
+        assert_eq!(result.to_string(), "heuristic: manhattan\n\
+            optimal: not guaranteed\n\
+            time complexity: 14 nodes generated, 6 expanded, 1 reopened\n\
+            memory complexity: 15 tree nodes, 8 max open set, 6 closed set\n\
+            elapsed: 1.5ms\n\
+            moves count: 3\n\
+            moves:\n\
+            RRD");
+        assert!(format!("{:#}", result).ends_with("moves:\nR2D"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_fields() {
+        let json = serde_json::to_value(result(2, 6)).unwrap();
+
+        assert_eq!(json["path"], serde_json::json!(["Up", "Up"]));
+        assert_eq!(json["path_cost"], 2);
+        assert_eq!(json["heuristic"], "test");
+        assert_eq!(json["optimal"], true);
+        let stats = json["stats"].as_object().unwrap();
+        let fields: Vec<&str> = stats.keys().map(String::as_str).collect();
+        assert_eq!(fields, ["closed_set", "elapsed", "max_open_set", "nodes_expanded", "nodes_generated", "nodes_reopened", "tree_nodes"]);
+        assert_eq!(stats["nodes_generated"], 6);
+    }
+}
@@ -1,108 +1,292 @@
-use std::collections::{HashSet, BinaryHeap};
+use std::collections::{HashMap, BinaryHeap};
+use std::time::Instant;
 use std::{error, fmt};
 
-use crate::board::Board;
-use crate::state::State;
+use crate::board::{Board, Tile, slides};
+use crate::bidirectional;
+use crate::state::{Arena, Known, State};
+use crate::packed_board::{PackedBoard, SearchKey};
 use crate::tile_move::Move;
-use crate::heuristic::Heuristic;
+use crate::heuristic::{Heuristic, DistanceFn};
+use crate::solve_result::{SolveResult, SolveStats};
+use crate::solve_options::SolveOptions;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Limit {
+    Expanded,
+    Memory,
+    Timeout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     UnmatchingSizes,
     Unsolvable,
+    LimitReached { limit: Limit, stats: SolveStats },
+    Cancelled,
 }
 
-impl error::Error for Error {
-    fn description(&self) -> &str {
+impl error::Error for Error { }
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::UnmatchingSizes => "sizes doesn't match",
-            Error::Unsolvable => "puzzle is unsolvable",
+            Limit::Expanded => f.write_str("expanded nodes"),
+            Limit::Memory => f.write_str("memory"),
+            Limit::Timeout => f.write_str("timeout"),
         }
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(error::Error::description(self))
+        match self {
+            Error::UnmatchingSizes => f.write_str("sizes doesn't match"),
+            Error::Unsolvable => f.write_str("puzzle is unsolvable"),
+            Error::LimitReached { limit, stats } => {
+                write!(f, "{} limit reached after {} expanded nodes", limit, stats.nodes_expanded)
+            },
+            Error::Cancelled => f.write_str("search cancelled"),
+        }
     }
 }
 
 #[derive(Debug)]
-pub struct Solver {
-    board: Board,
-    expected: Board,
+pub struct Solver<T: Tile = u8> {
+    board: Board<T>,
+    expected: Board<T>,
+    options: SolveOptions,
 }
 
-fn is_solvable(board: &Board, expected: &Board) -> bool {
+/// Whether `expected` can be reached from `board`, both boards must have the same size.
+///
+/// With an odd width a move never changes the inversions parity, with an even
+/// width a vertical move changes both the inversions parity and the zero row.
+pub fn is_solvable<T: Tile>(board: &Board<T>, expected: &Board<T>) -> bool {
     let mut board_inv = board.inversions();
     let mut expected_inv = expected.inversions();
 
-    if board.line_size % 2 == 0 {
-        board_inv += board.data.iter().position(|x| *x == 0).unwrap() / board.line_size;
-        expected_inv += expected.data.iter().position(|x| *x == 0).unwrap() / board.line_size;
+    if board.width.is_multiple_of(2) {
+        board_inv += board.zero() / board.width;
+        expected_inv += expected.zero() / expected.width;
     }
 
     board_inv % 2 == expected_inv % 2
 }
 
-impl Solver {
-    pub fn new(board: Board, expected: Board) -> Result<Self, Error> {
-        if board.data.len() != expected.data.len() {
+impl<T: Tile> Solver<T> {
+    pub fn new(board: Board<T>, expected: Board<T>) -> Result<Self, Error> {
+        if board.width != expected.width || board.height != expected.height {
             Err(Error::UnmatchingSizes)
         }
         else if !is_solvable(&board, &expected) {
             Err(Error::Unsolvable)
         } else {
-            Ok(Self { board, expected })
+            Ok(Self { board, expected, options: SolveOptions::default() })
+        }
+    }
+
+    pub fn with_options(self, options: SolveOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    pub fn solve<H: Heuristic<T>>(&self) -> Result<SolveResult, Error> {
+        self.solve_with(&H::new(&self.expected))
+    }
+
+    /// Same as `solve` with an already built heuristic, e.g. a loaded pattern database
+    /// or one picked at runtime through `HeuristicKind`.
+    pub fn solve_with<H: DistanceFn<T> + ?Sized>(&self, heuristic: &H) -> Result<SolveResult, Error> {
+        if self.board.data.len() <= PackedBoard::MAX_CELLS {
+            self.astar::<PackedBoard, H>(heuristic)
+        } else {
+            self.astar::<Box<[T]>, H>(heuristic)
         }
     }
 
-    pub fn solve<H: Heuristic>(&self) -> (usize, usize, Vec<Move>) {
-        let heuristic = H::new(&self.expected);
+    /// A* over an index of the best known cost of every board: a child is only
+    /// pushed if it improves on that cost, which reopens a closed board when the
+    /// heuristic is not consistent, the heap entries left behind are skipped when popped.
+    fn astar<K: SearchKey<T>, H: DistanceFn<T> + ?Sized>(&self, heuristic: &H) -> Result<SolveResult, Error> {
+        let start = Instant::now();
         let mut open_heap = BinaryHeap::new();
-        let mut close_set = HashSet::new();
-        let mut time_complexity = 0;
-        let mut mem_complexity = 1;
-        let mut mem_complexity_max = 0;
+        let mut index: HashMap<K, Known> = HashMap::new();
+        let mut open_len = 1;
+        let mut stats = SolveStats::default();
+        let mut scratch = self.board.clone();
+        let mut arena = Arena::new(K::encode(&self.board));
+        let expected = K::encode(&self.expected);
 
         // will be poped just after
-        open_heap.push(State{ cost: 0, distance: 0, board: self.board.clone(), parent: None });
-
-        loop {
-            let state = open_heap.pop().expect("invalid empty open heap");
-            mem_complexity -= 1;
-            if state.board.data == self.expected.data {
-                return (mem_complexity_max, time_complexity, state.build_path());
+        open_heap.push(State{ priority: 0, cost: 0, distance: 0, node: 0 });
+        index.insert(arena.key(0).clone(), Known { cost: 0, node: 0, closed: false });
+
+        // an exhausted open heap means every reachable state was seen
+        while let Some(state) = open_heap.pop() {
+            let key = arena.key(state.node).clone();
+            let known = index.get_mut(&key).unwrap();
+            if known.node != state.node || known.closed {
+                continue
+            }
+            known.closed = true;
+            open_len -= 1;
+            stats.closed_set += 1;
+
+            if key == expected {
+                let path = arena.path_to(state.node);
+                stats.tree_nodes = arena.len();
+                stats.elapsed = start.elapsed();
+                let optimal = self.options.evaluation.is_optimal();
+                return Ok(SolveResult { path_cost: path.len(), path, heuristic: heuristic.name(), optimal, stats });
             }
-            let children = state.children(&heuristic);
+            stats.tree_nodes = arena.len();
+            self.options.check(start, &stats, index.len())?;
+
+            let child_cost = state.cost + 1;
+            let children = state.children(&mut arena, &mut scratch, heuristic, self.options.evaluation,
+                |child| index.get(child).is_none_or(|known| child_cost < known.cost));
+            stats.nodes_expanded += 1;
+            stats.nodes_generated += children.len();
+
             for child in children {
-                if !close_set.contains(&child.board.data) {
-                    open_heap.push(child);
-                    time_complexity += 1;
-                    mem_complexity += 1;
+                let known = Known { cost: child_cost, node: child.node, closed: false };
+                match index.insert(arena.key(child.node).clone(), known) {
+                    None => open_len += 1,
+                    Some(Known { closed: true, .. }) => {
+                        stats.nodes_reopened += 1;
+                        stats.closed_set -= 1;
+                        open_len += 1;
+                    },
+                    Some(_) => (),
                 }
+                open_heap.push(child);
             }
-            if mem_complexity > mem_complexity_max {
-                mem_complexity_max = mem_complexity;
+            stats.max_open_set = stats.max_open_set.max(open_len);
+        }
+
+        Err(Error::Unsolvable)
+    }
+
+    /// Bidirectional A*, the goal side is guided by the same heuristic built from the start.
+    pub fn solve_bidirectional<H: Heuristic<T>>(&self) -> Result<SolveResult, Error> {
+        self.solve_bidirectional_with(&H::new(&self.expected), &H::new(&self.board))
+    }
+
+    /// `forward` estimates the distance to the goal and `backward` the distance to the start.
+    pub fn solve_bidirectional_with<F, B>(&self, forward: &F, backward: &B) -> Result<SolveResult, Error>
+        where F: DistanceFn<T> + ?Sized, B: DistanceFn<T> + ?Sized
+    {
+        bidirectional::solve(&self.board, &self.expected, &self.options, forward, backward)
+    }
+
+    /// Iterative deepening A*, memory stays linear in the solution depth.
+    pub fn solve_ida<H: Heuristic<T>>(&self) -> Result<SolveResult, Error> {
+        self.solve_ida_with(&H::new(&self.expected))
+    }
+
+    pub fn solve_ida_with<H: DistanceFn<T> + ?Sized>(&self, heuristic: &H) -> Result<SolveResult, Error> {
+        let start = Instant::now();
+        let mut search = IdaSearch {
+            heuristic,
+            options: &self.options,
+            start,
+            expected: &self.expected.data,
+            board: self.board.clone(),
+            path: Vec::new(),
+            stats: SolveStats::default(),
+        };
+
+        let mut bound = heuristic.distance(&search.board);
+        loop {
+            match search.search(0, bound)? {
+                IdaOutcome::Found => {
+                    let mut stats = search.stats;
+                    stats.tree_nodes = search.path.len() + 1;
+                    stats.elapsed = start.elapsed();
+                    let path = search.path;
+                    return Ok(SolveResult { path_cost: path.len(), path, heuristic: heuristic.name(), optimal: true, stats });
+                },
+                IdaOutcome::Bound(usize::MAX) => return Err(Error::Unsolvable),
+                IdaOutcome::Bound(next_bound) => bound = next_bound,
             }
-            close_set.insert(state.board.data);
         }
     }
+}
 
+enum IdaOutcome {
+    Found,
+    Bound(usize),
+}
+
+struct IdaSearch<'a, T: Tile, H: ?Sized> {
+    heuristic: &'a H,
+    options: &'a SolveOptions,
+    start: Instant,
+    expected: &'a [T],
+    board: Board<T>,
+    path: Vec<Move>,
+    stats: SolveStats,
+}
+
+impl<'a, T: Tile, H: DistanceFn<T> + ?Sized> IdaSearch<'a, T, H> {
+    fn search(&mut self, cost: usize, bound: usize) -> Result<IdaOutcome, Error> {
+        let total = cost + self.heuristic.distance(&self.board);
+        if total > bound {
+            return Ok(IdaOutcome::Bound(total))
+        }
+        if &self.board.data[..] == self.expected {
+            return Ok(IdaOutcome::Found)
+        }
+        // only the boards of the current path are held
+        self.stats.tree_nodes = self.path.len() + 1;
+        self.options.check(self.start, &self.stats, self.stats.tree_nodes)?;
+
+        let zero = self.board.zero();
+        let mut min_bound = usize::MAX;
+        self.stats.nodes_expanded += 1;
+
+        // every move is described by the tile sliding into the zero
+        for &(move_, tile) in &slides(zero, self.board.width, self.board.height) {
+            let tile = match tile {
+                Some(tile) if self.path.last() != Some(&move_.inverse()) => tile,
+                _ => continue,
+            };
+
+            self.board.data.swap(zero, tile);
+            self.path.push(move_);
+            self.stats.nodes_generated += 1;
+
+            match self.search(cost + 1, bound)? {
+                IdaOutcome::Found => return Ok(IdaOutcome::Found),
+                IdaOutcome::Bound(next_bound) => min_bound = min_bound.min(next_bound),
+            }
+
+            self.path.pop();
+            self.board.data.swap(zero, tile);
+        }
+
+        Ok(IdaOutcome::Bound(min_bound))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use heuristic::Manhattan;
-    use heuristic::Dijkstra;
-    use heuristic::MissPlaced;
-    use heuristic::Euclidean;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use crate::evaluation::{Evaluation, Weight};
+    use crate::generator::Generator;
+    use crate::heuristic::Manhattan;
+    use crate::heuristic::Dijkstra;
+    use crate::heuristic::MissPlaced;
+    use crate::heuristic::Euclidean;
+    use crate::heuristic::LinearConflict;
+    use crate::heuristic::WalkingDistance;
 
     #[test]
     fn unmatching_sizes() {
-        let board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
-        let expected = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0].into_boxed_slice(), 4);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0].into_boxed_slice(), 4);
 
         let solver_result = Solver::new(board, expected);
 
@@ -113,8 +297,8 @@ mod tests {
 
     #[test]
     fn unsolvable_simple() {
-        let board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
-        let expected = Board::new(vec![1, 2, 3, 4, 5, 6, 8, 7, 0].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 8, 7, 0].into_boxed_slice(), 3);
 
         let solver_result = Solver::new(board, expected);
 
@@ -125,8 +309,8 @@ mod tests {
 
     #[test]
     fn unsolvable_medium() {
-        let board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
-        let expected = Board::new(vec![2, 1, 3, 5, 4, 6, 8, 7, 0].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![2, 1, 3, 5, 4, 6, 8, 7, 0].into_boxed_slice(), 3);
 
         let solver_result = Solver::new(board, expected);
 
@@ -137,8 +321,8 @@ mod tests {
 
     #[test]
     fn unsolvable_hard() {
-        let board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
-        let expected = Board::new(vec![2, 1, 5, 4, 3, 6, 8, 7, 0].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![2, 1, 5, 4, 3, 6, 8, 7, 0].into_boxed_slice(), 3);
 
         let solver_result = Solver::new(board, expected);
 
@@ -149,8 +333,8 @@ mod tests {
 
     #[test]
     fn solvable() {
-        let board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
-        let expected = Board::new(vec![1, 2, 3, 4, 5, 6, 0, 7, 8].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 0, 7, 8].into_boxed_slice(), 3);
 
         let solver_result = Solver::new(board, expected);
 
@@ -159,15 +343,18 @@ mod tests {
 
     #[test]
     fn state_tree() {
-        let board = Board::new(vec![1, 2, 3, 4, 0, 6, 7, 8, 5].into_boxed_slice(), 3);
-        let expected = Board::new(vec![1, 2, 3, 4, 5, 6, 0, 7, 8].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 0, 6, 7, 8, 5].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 0, 7, 8].into_boxed_slice(), 3);
         let dijkstra = Dijkstra::new(&expected);
 
         let mut open_heap = BinaryHeap::new();
 
-        let parent = State { cost: 0, distance: 0, board: board, parent: None };
+        let mut scratch = board.clone();
+        let mut arena = Arena::new(PackedBoard::new(&board).unwrap());
+        let parent = State { priority: 0, cost: 0, distance: 0, node: 0 };
 
-        let children = parent.children(&dijkstra);
+        let children = parent.children(&mut arena, &mut scratch, &dijkstra, Evaluation::AStar, |_| true);
+        assert_eq!(children.len(), 4);
         {
             {
                 for child in children {
@@ -175,7 +362,7 @@ mod tests {
                 }
             }
             let parent = open_heap.pop().unwrap();
-            let children = parent.children(&dijkstra);
+            let children = parent.children(&mut arena, &mut scratch, &dijkstra, Evaluation::AStar, |_| true);
             {
                 for child in children {
                     open_heap.push(child);
@@ -187,49 +374,320 @@ mod tests {
 
     #[test]
     fn solver_3x3_manhattan() {
-        let board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
-        let expected = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
 
         let solver = Solver::new(board, expected).unwrap();
-        let result = solver.solve::<Manhattan>();
+        let result = solver.solve::<Manhattan>().unwrap();
 
         let expected_result = &[Move::Right, Move::Down, Move::Right];
-        assert_eq!(&result, expected_result)
+        assert_eq!(&result.path[..], expected_result)
     }
 
     #[test]
     fn solver_3x3_dijkstra() {
-        let board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
-        let expected = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
 
         let solver = Solver::new(board, expected).unwrap();
-        let result = solver.solve::<Dijkstra>();
+        let result = solver.solve::<Dijkstra>().unwrap();
 
         let expected_result = &[Move::Right, Move::Down, Move::Right];
-        assert_eq!(&result, expected_result)
+        assert_eq!(&result.path[..], expected_result)
     }
 
     #[test]
     fn solver_3x3_euclidean() {
-        let board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
-        let expected = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
 
         let solver = Solver::new(board, expected).unwrap();
-        let result = solver.solve::<Euclidean>();
+        let result = solver.solve::<Euclidean>().unwrap();
 
         let expected_result = &[Move::Right, Move::Down, Move::Right];
-        assert_eq!(&result, expected_result)
+        assert_eq!(&result.path[..], expected_result)
     }
 
     #[test]
     fn solver_3x3_miss_placed() {
-        let board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
-        let expected = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
+
+        let solver = Solver::new(board, expected).unwrap();
+        let result = solver.solve::<MissPlaced>().unwrap();
+
+        let expected_result = &[Move::Right, Move::Down, Move::Right];
+        assert_eq!(&result.path[..], expected_result)
+    }
+
+    #[test]
+    fn solver_3x3_ida_manhattan() {
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
 
         let solver = Solver::new(board, expected).unwrap();
-        let result = solver.solve::<MissPlaced>();
+        let result = solver.solve_ida::<Manhattan>().unwrap();
 
         let expected_result = &[Move::Right, Move::Down, Move::Right];
-        assert_eq!(&result, expected_result)
+        assert_eq!(&result.path[..], expected_result);
+        assert_eq!((result.stats.tree_nodes, result.stats.max_open_set, result.stats.closed_set), (4, 0, 0));
+    }
+
+    #[test]
+    fn solver_3x3_ida_same_length_as_astar() {
+        let board: Board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+
+        let solver = Solver::new(board, expected).unwrap();
+        let ida_result = solver.solve_ida::<Manhattan>().unwrap();
+        let dijkstra_result = solver.solve::<Dijkstra>().unwrap();
+
+        assert_eq!(ida_result.path_cost, dijkstra_result.path_cost)
+    }
+
+    #[test]
+    fn solver_3x3_linear_conflict() {
+        let board: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 0, 4, 6, 7, 5, 8].into_boxed_slice(), 3);
+
+        let solver = Solver::new(board, expected).unwrap();
+        let result = solver.solve::<LinearConflict>().unwrap();
+
+        let expected_result = &[Move::Right, Move::Down, Move::Right];
+        assert_eq!(&result.path[..], expected_result)
+    }
+
+    #[test]
+    fn linear_conflict_reversed_row() {
+        let expected: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let board: Board = Board::new(vec![3, 2, 1, 4, 5, 6, 7, 8, 0].into_boxed_slice(), 3);
+        let linear_conflict = LinearConflict::new(&expected);
+        let manhattan = Manhattan::new(&expected);
+
+        // two of the three tiles have to leave the row
+        assert_eq!(linear_conflict.distance(&board), manhattan.distance(&board) + 4)
+    }
+
+    #[test]
+    fn solver_3x3_walking_distance() {
+        let board: Board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+
+        let solver = Solver::new(board, expected).unwrap();
+        let walking_distance_result = solver.solve_ida::<WalkingDistance>().unwrap();
+        let dijkstra_result = solver.solve::<Dijkstra>().unwrap();
+
+        assert_eq!(walking_distance_result.path_cost, dijkstra_result.path_cost)
+    }
+
+    /// Admissible but not consistent, its estimate drops to zero on every other zero cell.
+    struct Flickering {
+        expected: Board,
+    }
+
+    impl DistanceFn for Flickering {
+        fn name(&self) -> &'static str {
+            "flickering"
+        }
+
+        fn distance(&self, current: &Board) -> usize {
+            if current.zero() % 2 == 1 {
+                return 0
+            }
+            current.data.iter().zip(self.expected.data.iter())
+                .filter(|&(&tile, &expected)| tile != 0 && tile != expected)
+                .count()
+        }
+    }
+
+    #[test]
+    fn no_duplicate_expansion() {
+        let board: Board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let solver = Solver::new(board, expected).unwrap();
+
+        // a consistent heuristic never expands a board twice
+        let stats = solver.solve::<Dijkstra>().unwrap().stats;
+        assert_eq!(stats.nodes_reopened, 0);
+        assert_eq!(stats.nodes_expanded + 1, stats.closed_set);
+    }
+
+    #[test]
+    fn inconsistent_heuristic_reopens() {
+        let expected: Board = Board::snail(3);
+        let board = Generator::new(0).shuffle(&expected);
+        let solver = Solver::new(board, expected.clone()).unwrap();
+
+        let result = solver.solve_with(&Flickering { expected }).unwrap();
+        assert!(result.stats.nodes_reopened > 0);
+        assert_eq!(result.path_cost, solver.solve::<Dijkstra>().unwrap().path_cost);
+    }
+
+    #[test]
+    fn expanded_limit() {
+        let board: Board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let options = SolveOptions { max_expanded: Some(10), ..SolveOptions::default() };
+
+        let solver = Solver::new(board, expected).unwrap().with_options(options);
+
+        for result in [solver.solve::<Dijkstra>(), solver.solve_ida::<Dijkstra>()] {
+            match result {
+                Err(Error::LimitReached { limit: Limit::Expanded, stats }) => assert_eq!(stats.nodes_expanded, 10),
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn memory_limit() {
+        let board: Board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let options = SolveOptions { max_memory: Some(100), ..SolveOptions::default() };
+
+        let solver = Solver::new(board, expected).unwrap().with_options(options);
+
+        match solver.solve::<Dijkstra>() {
+            Err(Error::LimitReached { limit: Limit::Memory, .. }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn memory_limit_counts_states() {
+        let expected: Board = Board::snail(3);
+        let board = Generator::new(0).shuffle(&expected);
+        let heuristic = Flickering { expected: expected.clone() };
+        let unlimited = Solver::new(board.clone(), expected.clone()).unwrap().solve_with(&heuristic).unwrap();
+
+        // reopened boards leave stale tree nodes behind which do not count
+        let states = unlimited.stats.nodes_expanded + 1 + unlimited.stats.max_open_set;
+        assert!(unlimited.stats.tree_nodes > states, "{:?}", unlimited.stats);
+        let options = SolveOptions { max_memory: Some(states), ..SolveOptions::default() };
+        let limited = Solver::new(board, expected).unwrap().with_options(options).solve_with(&heuristic).unwrap();
+        assert_eq!(limited.path, unlimited.path);
+    }
+
+    #[test]
+    fn cancelled() {
+        let board: Board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let cancel = Arc::new(AtomicBool::new(true));
+        let options = SolveOptions { cancel: Some(cancel), ..SolveOptions::default() };
+
+        let solver = Solver::new(board, expected).unwrap().with_options(options);
+
+        assert_eq!(solver.solve::<Manhattan>(), Err(Error::Cancelled));
+        assert_eq!(solver.solve_ida::<Manhattan>(), Err(Error::Cancelled));
+    }
+
+    #[test]
+    fn evaluations() {
+        let board: Board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+        let optimal_cost = Solver::new(board.clone(), expected.clone()).unwrap().solve::<Manhattan>().unwrap().path_cost;
+
+        let evaluations = [
+            Evaluation::UniformCost,
+            Evaluation::AStar,
+            Evaluation::Weighted(Weight::new(3, 2)),
+            Evaluation::Greedy,
+        ];
+        for &evaluation in &evaluations {
+            let options = SolveOptions { evaluation, ..SolveOptions::default() };
+            let solver = Solver::new(board.clone(), expected.clone()).unwrap().with_options(options);
+            let result = solver.solve::<Manhattan>().unwrap();
+
+            assert_eq!(result.optimal, evaluation.is_optimal());
+            if result.optimal {
+                assert_eq!(result.path_cost, optimal_cost);
+            } else {
+                assert!(result.path_cost >= optimal_cost);
+            }
+        }
+    }
+
+    #[test]
+    fn bidirectional() {
+        let board: Board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let expected: Board = Board::new(vec![1, 2, 3, 8, 0, 4, 7, 6, 5].into_boxed_slice(), 3);
+
+        let solver = Solver::new(board.clone(), expected.clone()).unwrap();
+        let optimal_cost = solver.solve::<Dijkstra>().unwrap().path_cost;
+
+        for result in [solver.solve_bidirectional::<Manhattan>(), solver.solve_bidirectional::<Dijkstra>()] {
+            let result = result.unwrap();
+            assert_eq!(result.path_cost, optimal_cost);
+
+            let end = result.path.iter().fold(board.clone(), |board, &move_| board.moved(move_).unwrap());
+            assert_eq!(end, expected);
+        }
+    }
+
+    #[test]
+    fn bidirectional_stats() {
+        let expected: Board = Board::snail(3);
+        let board = Generator::new(0).shuffle(&expected);
+        let solver = Solver::new(board, expected.clone()).unwrap();
+
+        let stats = solver.solve_bidirectional::<Manhattan>().unwrap().stats;
+        assert_eq!((stats.nodes_reopened, stats.closed_set), (0, stats.nodes_expanded));
+        assert!(stats.closed_set < stats.tree_nodes && stats.max_open_set <= stats.tree_nodes, "{:?}", stats);
+
+        // every expansion closes a board, a reopening opens it again
+        let stats = solver.solve_bidirectional_with(&Flickering { expected }, &Dijkstra::new(&solver.board)).unwrap().stats;
+        assert!(stats.nodes_reopened > 0);
+        assert_eq!(stats.closed_set + stats.nodes_reopened, stats.nodes_expanded);
+    }
+
+    #[test]
+    fn bidirectional_already_solved() {
+        let board: Board = Board::snail(3);
+
+        let solver = Solver::new(board.clone(), board).unwrap();
+
+        assert_eq!(solver.solve_bidirectional::<Manhattan>().unwrap().path_cost, 0);
+    }
+
+    #[test]
+    fn rectangle_solvability() {
+        let expected: Board = Board::row_major_with_size(4, 2);
+        let swapped = Board::with_size(vec![2, 1, 3, 4, 5, 6, 7, 0].into_boxed_slice(), 4, 2);
+        let moved = Board::with_size(vec![1, 2, 3, 0, 5, 6, 7, 4].into_boxed_slice(), 4, 2);
+
+        assert_eq!(Solver::new(swapped, expected.clone()).unwrap_err(), Error::Unsolvable);
+        assert!(Solver::new(moved, expected).is_ok());
+    }
+
+    #[test]
+    fn solver_rectangles() {
+        for &(width, height) in &[(2, 4), (3, 5), (4, 2)] {
+            let expected: Board = Board::snail_with_size(width, height);
+            let board = Generator::new(3).random_walk(&expected, 20);
+            let solver = Solver::new(board.clone(), expected.clone()).unwrap();
+
+            let costs: Vec<usize> = [
+                solver.solve::<Manhattan>(),
+                solver.solve::<LinearConflict>(),
+                solver.solve::<WalkingDistance>(),
+                solver.solve_ida::<Manhattan>(),
+                solver.solve_bidirectional::<Manhattan>(),
+            ].iter().map(|result| result.as_ref().unwrap().path_cost).collect();
+
+            assert!(costs.iter().all(|&cost| cost == costs[0] && cost <= 20), "{:?}", costs);
+        }
+    }
+
+    #[test]
+    fn solver_wide_tiles() {
+        // 17x17 has 288 tiles, more than a u8 holds
+        let expected: Board<u16> = Board::snail(17);
+        let board = Generator::new(5).random_walk(&expected, 12);
+        let solver = Solver::new(board, expected).unwrap();
+
+        let astar = solver.solve::<LinearConflict>().unwrap();
+        let ida = solver.solve_ida::<Manhattan>().unwrap();
+        assert!(astar.path_cost <= 12);
+        assert_eq!(astar.path_cost, ida.path_cost);
     }
 }
@@ -1,49 +1,109 @@
-use std::rc::Rc;
-use crate::board::Board;
+use crate::board::{Board, Tile, slides};
+use crate::packed_board::SearchKey;
 use crate::tile_move::Move;
-use crate::heuristic::Heuristic;
+use crate::heuristic::DistanceFn;
+use crate::evaluation::Evaluation;
 use std::cmp::Ordering;
 
+/// Search tree node, `key` is the board in the representation of the open and closed sets.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Node<K> {
+    pub key: K,
+    pub parent: Option<usize>,
+    /// Move which reached this node from its parent.
+    pub move_: Option<Move>,
+}
+
+/// Every node generated by a search, parents are referred to by index
+/// so a node is stored once whatever the number of its children.
+#[derive(Debug)]
+pub struct Arena<K> {
+    nodes: Vec<Node<K>>,
+}
+
+impl<K> Arena<K> {
+    pub fn new(root: K) -> Self {
+        Self { nodes: vec![Node { key: root, parent: None, move_: None }] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn key(&self, index: usize) -> &K {
+        &self.nodes[index].key
+    }
+
+    /// Moves from the root to the node at `index`.
+    pub fn path_to(&self, mut index: usize) -> Vec<Move> {
+        let mut path = Vec::new();
+        while let Node { parent: Some(parent), move_: Some(move_), .. } = self.nodes[index] {
+            path.push(move_);
+            index = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Best known way to a board, indexed by its key for both the open and closed sets.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Known {
+    pub cost: usize,
+    /// Arena node reached with `cost`, the open entries of other nodes with this key are stale.
+    pub node: usize,
+    pub closed: bool,
+}
 
+/// Open set entry pointing to its node in the arena.
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct State {
+    pub priority: usize,
     pub cost: usize,
     pub distance: usize,
-    pub board: Board,
-    pub parent: Option<Rc<State>>,
+    pub node: usize,
 }
 
 impl State {
-    pub fn children<H: Heuristic>(&self, heuristic: &H) -> Vec<State> {
-        let parent = Rc::new(self.clone());
-        self.board.children().into_iter().map(|board| Self {
-            cost: self.cost + 1,
-            distance: heuristic.distance(&board),
-            board: board,
-            parent: Some(parent.clone())
-        }).collect()
-    }
+    /// Pushes the children accepted by `keep` into the arena, `board` is a scratch
+    /// board of the right size whose tiles are overwritten.
+    pub fn children<K, T, H>(&self, arena: &mut Arena<K>, board: &mut Board<T>, heuristic: &H,
+        evaluation: Evaluation, mut keep: impl FnMut(&K) -> bool) -> Vec<State>
+        where T: Tile, K: SearchKey<T>, H: DistanceFn<T> + ?Sized
+    {
+        let key = arena.key(self.node).clone();
+        key.decode_into(board);
+        let zero = board.zero();
 
-    pub fn build_path(&self) -> Vec<Move> {
-        fn precedent_move(state: &State, path: &mut Vec<Move>) {
-            if let Some(ref parent) = state.parent {
-                precedent_move(parent, path);
-                let move_ = Move::new(&parent.board, &state.board);
-                path.push(move_);
+        slides(zero, board.width, board.height).iter().filter_map(|&(move_, cell)| {
+            let cell = cell?;
+            let child = key.slide(zero, cell);
+            if !keep(&child) {
+                return None
             }
-        }
-        let mut path = Vec::new();
-        precedent_move(self, &mut path);
-        path
+
+            board.data.swap(zero, cell);
+            let distance = heuristic.distance(board);
+            board.data.swap(zero, cell);
+
+            arena.nodes.push(Node { key: child, parent: Some(self.node), move_: Some(move_) });
+            let cost = self.cost + 1;
+            Some(Self {
+                priority: evaluation.priority(cost, distance),
+                cost,
+                distance,
+                node: arena.len() - 1,
+            })
+        }).collect()
     }
 }
 
 impl Ord for State {
     fn cmp(&self, other: &State) -> Ordering {
-        // Notice that the we flip the ordering on costs.
-        let self_cost = self.distance * self.cost;
-        let other_cost = other.distance * other.cost;
-        other_cost.cmp(&self_cost)
+        // Notice that the we flip the ordering on priorities,
+        // ties go to the deepest state which is the closest to the goal.
+        other.priority.cmp(&self.priority)
+            .then_with(|| self.cost.cmp(&other.cost))
     }
 }
 
@@ -51,4 +111,24 @@ impl PartialOrd for State {
     fn partial_cmp(&self, other: &State) -> Option<Ordering> {
         Some(self.cmp(other))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deep_path() {
+        // a recursive walk would overflow the stack
+        let mut arena = Arena::new(0);
+        for i in 0..1_000_000 {
+            let move_ = if i % 2 == 0 { Move::Up } else { Move::Left };
+            arena.nodes.push(Node { key: i + 1, parent: Some(i), move_: Some(move_) });
+        }
+
+        let path = arena.path_to(arena.len() - 1);
+        assert_eq!(path.len(), 1_000_000);
+        assert_eq!(&path[..2], &[Move::Up, Move::Left]);
+        assert!(arena.path_to(0).is_empty());
+    }
+}
@@ -1,22 +1,37 @@
-use crate::board::Board;
+use std::str::FromStr;
+use std::{error, fmt};
+
+use crate::board::{Board, Tile};
 
 #[derive (Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Move {
     Up,
     Down,
     Left,
-    Right,    
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMove(pub String);
+
+impl fmt::Display for InvalidMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid move {:?}, expected U, D, L or R", self.0)
+    }
 }
 
+impl error::Error for InvalidMove { }
+
 impl Move {
-    pub fn new(parent: &Board, board: &Board) -> Self {
-        let line_size = board.line_size;
-        let zero = board.data.iter().position(|&x| x == 0).unwrap();
-        let parent_zero = parent.data.iter().position(|&x| x == 0).unwrap();
+    pub fn new<T: Tile>(parent: &Board<T>, board: &Board<T>) -> Self {
+        let width = board.width;
+        let zero = board.zero();
+        let parent_zero = parent.zero();
 
-        if zero == parent_zero + line_size {
+        if zero == parent_zero + width {
             Move::Up
-        } else if zero + line_size == parent_zero {
+        } else if zero + width == parent_zero {
             Move::Down
         } else if zero + 1 == parent_zero {
             Move::Right
@@ -24,4 +39,67 @@ impl Move {
             Move::Left
         }
     }
-}
\ No newline at end of file
+
+    /// First letter of the direction, as in move strings such as `UDLR`.
+    pub fn letter(self) -> char {
+        match self {
+            Move::Up => 'U',
+            Move::Down => 'D',
+            Move::Left => 'L',
+            Move::Right => 'R',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            'U' => Some(Move::Up),
+            'D' => Some(Move::Down),
+            'L' => Some(Move::Left),
+            'R' => Some(Move::Right),
+            _ => None,
+        }
+    }
+
+    pub fn inverse(self) -> Self {
+        match self {
+            Move::Up => Move::Down,
+            Move::Down => Move::Up,
+            Move::Left => Move::Right,
+            Move::Right => Move::Left,
+        }
+    }
+}
+
+/// Single letter notation, `U`, `D`, `L` or `R`.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+impl FromStr for Move {
+    type Err = InvalidMove;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut letters = string.trim().chars();
+        match (letters.next(), letters.next()) {
+            (Some(letter), None) => Move::from_letter(letter).ok_or_else(|| InvalidMove(string.to_string())),
+            _ => Err(InvalidMove(string.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_round_trip() {
+        for &move_ in &[Move::Up, Move::Down, Move::Left, Move::Right] {
+            assert_eq!(move_.to_string().parse(), Ok(move_));
+            assert_eq!(move_.to_string().to_lowercase().parse(), Ok(move_));
+        }
+        assert!("UD".parse::<Move>().is_err());
+        assert!("".parse::<Move>().is_err());
+    }
+}
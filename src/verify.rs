@@ -0,0 +1,66 @@
+use std::{error, fmt};
+
+use crate::board::{Board, Tile, IllegalMove};
+use crate::tile_move::Move;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError<T: Tile = u8> {
+    UnmatchingSizes,
+    IllegalMove(IllegalMove),
+    /// Every move is legal but they end on this board instead of the goal.
+    NotSolved(Board<T>),
+}
+
+impl<T: Tile> fmt::Display for VerifyError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::UnmatchingSizes => write!(f, "the start and the goal sizes don't match"),
+            VerifyError::IllegalMove(error) => write!(f, "{}", error),
+            VerifyError::NotSolved(board) => write!(f, "the moves end on this board instead of the goal\n{}", board.to_string().trim_end()),
+        }
+    }
+}
+
+impl<T: Tile> error::Error for VerifyError<T> { }
+
+/// Checks that `moves` lead from `start` to `goal`, e.g. a solution found by another solver.
+pub fn verify<T: Tile>(start: &Board<T>, goal: &Board<T>, moves: &[Move]) -> Result<(), VerifyError<T>> {
+    if start.width != goal.width || start.height != goal.height {
+        return Err(VerifyError::UnmatchingSizes)
+    }
+
+    let mut board = start.clone();
+    board.apply_all(moves).map_err(VerifyError::IllegalMove)?;
+    if board != *goal {
+        return Err(VerifyError::NotSolved(board))
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+    use crate::heuristic::LinearConflict;
+
+    #[test]
+    fn solver_paths_verify() {
+        let start: Board = Board::new(vec![3, 2, 6, 1, 4, 0, 8, 7, 5].into_boxed_slice(), 3);
+        let goal: Board = Board::snail(3);
+        let path = Solver::new(start.clone(), goal.clone()).unwrap().solve::<LinearConflict>().unwrap().path;
+
+        assert_eq!(verify(&start, &goal, &path), Ok(()));
+    }
+
+    #[test]
+    fn failures() {
+        let goal: Board = Board::row_major(3);
+        let start: Board = Board::new(vec![1, 2, 3, 4, 5, 6, 7, 0, 8].into_boxed_slice(), 3);
+
+        assert_eq!(verify(&start, &goal, &[Move::Left]), Ok(()));
+        assert_eq!(verify(&start, &goal, &[Move::Left, Move::Left]),
+            Err(VerifyError::IllegalMove(IllegalMove { step: 1, move_: Move::Left })));
+        assert_eq!(verify(&start, &goal, &[]), Err(VerifyError::NotSolved(start.clone())));
+        assert_eq!(verify(&start, &Board::row_major(4), &[]), Err(VerifyError::UnmatchingSizes));
+    }
+}